@@ -1,135 +1,425 @@
-use futures::{stream, StreamExt};
-use reqwest::{Client, Proxy};
+use futures::StreamExt;
+use reqwest::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    redirect::Policy,
+    Client, ClientBuilder, Proxy,
+};
+use serde::Serialize;
 use std::{
-    collections::HashSet,
-    iter::FromIterator,
+    net::SocketAddr,
+    sync::Arc,
     time::{Duration, Instant},
 };
+use trust_dns_resolver::config::ResolverConfig;
+use url::Url;
 
 use crate::dns;
-use crate::ports;
-use crate::{modules, modules::HttpModule, modules::Subdomain, Error};
-// calls all modules
-pub fn modules() {
-    let http_modules = modules::all_http_modules();
-    let subdomain_modules = modules::all_subdomains_modules();
+use crate::ports::IpVersion;
+use crate::report::{OutputFormat, Reporter};
+use crate::scanner::{Concurrency, Scanner};
+use crate::takeover::{self, Fingerprint, Takeover};
+use crate::targets::Target;
+use crate::throttle::Limits;
+use crate::{
+    modules,
+    modules::{HttpModule, ModuleFilter, ModuleInfo},
+    Error,
+};
+// every module tricoder has, the templates of these paths included
+pub fn modules_info(templates: &[String]) -> Result<Vec<ModuleInfo>, Error> {
+    let dns_resolver = dns::Resolver::default();
+    let mut subdomain_modules = modules::all_subdomains_modules(&dns_resolver);
+    // only runs with --wordlist but is still listed
+    subdomain_modules.push(Box::new(modules::Bruteforce::new(
+        dns_resolver,
+        Vec::new(),
+        1,
+    )));
+    let takeover = Takeover::new(takeover::default_fingerprints());
 
-    println!("subdomain modules");
-    for module in subdomain_modules {
-        println!("  {}:{}", module.name(), module.description());
+    let mut infos: Vec<ModuleInfo> = subdomain_modules
+        .iter()
+        .map(|module| ModuleInfo::new("subdomains", module.as_ref()))
+        .collect();
+    infos.extend(
+        modules::http_modules(templates)?
+            .iter()
+            .map(|module| ModuleInfo::new("http", module.as_ref())),
+    );
+    // only runs with --git-dump but is still listed
+    infos.push(ModuleInfo::new("http", &modules::GitDump::new("")));
+    infos.push(ModuleInfo::new("dns", &takeover));
+    Ok(infos)
+}
+
+// tricoder modules
+pub fn modules(json: bool, templates: &[String]) -> Result<(), Error> {
+    let infos = modules_info(templates)?;
+    if json {
+        println!("{}", to_json(&infos)?);
+        return Ok(());
     }
+    for kind in ["subdomains", "http", "dns"] {
+        println!("{} modules", kind);
+        for info in infos.iter().filter(|info| info.kind == kind) {
+            let severity = info
+                .severity
+                .map(|severity| severity.to_string())
+                .unwrap_or_default();
+            println!(
+                "  {:<50} {:<9} {:<24} {}",
+                info.name,
+                severity,
+                info.tags.join(","),
+                info.description
+            );
+            for request in &info.requests {
+                println!("      {}", request);
+            }
+        }
+    }
+    println!("intrusive modules only run with --intrusive");
+    Ok(())
+}
+
+// tricoder modules info <name>
+pub fn module_info(name: &str, json: bool, templates: &[String]) -> Result<(), Error> {
+    let info = modules_info(templates)?
+        .into_iter()
+        .find(|info| info.name == name)
+        .ok_or_else(|| Error::UnknownModule(name.to_string()))?;
+    if json {
+        println!("{}", to_json(&info)?);
+        return Ok(());
+    }
+    println!("name:        {}", info.name);
+    println!("kind:        {}", info.kind);
+    println!("description: {}", info.description);
+    if let Some(severity) = info.severity {
+        println!("severity:    {}", severity);
+    }
+    println!("tags:        {}", info.tags.join(", "));
+    if info.intrusive {
+        println!("intrusive:   yes, it sends payloads and only runs with --intrusive");
+    } else {
+        println!("intrusive:   no");
+    }
+    println!("requests:");
+    for request in &info.requests {
+        println!("  {}", request);
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    serde_json::to_string_pretty(value).map_err(|err| Error::InvalidOutputFormat(err.to_string()))
 }
 
-pub fn scan(target: &str) -> Result<(), Error> {
-    log::info!("scanning:{}", target);
+// what the user asked for on the command line
+pub struct ScanOptions {
+    pub targets: Vec<Target>,
+    pub ports: Vec<u16>,
+    pub ip_version: IpVersion,
+    pub service_detection: bool,
+    // how many times hostnames found while scanning are fed back
+    pub max_depth: usize,
+    // words for subdomains/bruteforce, the module is off without them
+    pub wordlist: Option<Vec<String>>,
+    // the dns servers queries are spread over
+    pub resolvers: Vec<ResolverConfig>,
+    // max queries per second to each of them
+    pub resolver_rate: Option<u32>,
+    pub takeover_fingerprints: Vec<Fingerprint>,
+    // http, https, socks5 or socks5h url every http request goes through
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    // sent with every request to the targets, see request_headers
+    pub headers: HeaderMap,
+    pub verify_tls: bool,
+    // how long a subdomain module gets for a domain
+    pub subdomains_timeout: Duration,
+    // for one request to a target
+    pub http_timeout: Duration,
+    // for a service or tls probe of an open port
+    pub probe_timeout: Duration,
+    pub concurrency: Concurrency,
+    // requests per second and jitter of the http requests and port connects
+    pub limits: Limits,
+    pub modules: ModuleFilter,
+    // the rust modules and the templates, before the filter
+    pub http_modules: Vec<Arc<dyn HttpModule>>,
+    pub output: OutputFormat,
+}
+
+pub fn scan(options: ScanOptions) -> Result<(), Error> {
+    let ScanOptions {
+        targets,
+        ports,
+        ip_version,
+        service_detection,
+        max_depth,
+        wordlist,
+        resolvers,
+        resolver_rate,
+        takeover_fingerprints,
+        proxy,
+        user_agent,
+        headers,
+        verify_tls,
+        subdomains_timeout,
+        http_timeout,
+        probe_timeout,
+        concurrency,
+        limits,
+        modules: module_filter,
+        http_modules,
+        output,
+    } = options;
     //creates runtime
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .expect("building tokio runtime");
-    //sets response time
-    let http_timeout = Duration::from_secs(10);
     // builds client
-    //tor is good but comment this if you dont get shit
+    //tor is good: --proxy socks5h://127.0.0.1:9050
     //you never know when its tors fault for beign blocked
-    // let proxy = reqwest::Proxy::all("socks5h://127.0.0.1:9050").expect("no tor?");
-    let http_client = Client::builder()
+    // the headers (auth, cookies) are only for the targets, not the third party apis
+    let http_client = client_builder(&proxy, &user_agent, verify_tls)?
+        .default_headers(headers.clone())
         .timeout(http_timeout)
-        .danger_accept_invalid_certs(true)
-        // .user_agent("")
-        // .proxy(proxy)
         .build()?;
-
-    // builds dns
-    let dns_resolver = dns::new_resolver();
-    // sets limits for concur
-    let subdomains_concur = 20;
-    let dns_concur = 100;
-    let ports_concur = 200;
-    let vuln_concur = 20;
+    // same but stops at redirects so we see where they go
+    let crawl_client = client_builder(&proxy, &user_agent, verify_tls)?
+        .default_headers(headers)
+        .timeout(http_timeout)
+        .redirect(Policy::none())
+        .build()?;
+    let subdomains_client = client_builder(&proxy, &user_agent, verify_tls)?
+        .timeout(Duration::from_secs(60))
+        .build()?;
+    let mut scanner = Scanner::builder()
+        .targets(targets)
+        .ports(ports)
+        .ip_version(ip_version)
+        .service_detection(service_detection)
+        .depth(max_depth)
+        .http_modules(http_modules)
+        .modules(module_filter)
+        .takeover_fingerprints(takeover_fingerprints)
+        .client(http_client)
+        .crawl_client(crawl_client)
+        .subdomains_client(subdomains_client)
+        .resolver(dns::new_resolver(resolvers, resolver_rate))
+        .limits(limits)
+        .concurrency(concurrency)
+        .subdomains_timeout(subdomains_timeout)
+        .probe_timeout(probe_timeout)
+        .socks_proxy(socks_proxy(&proxy)?);
+    if let Some(words) = wordlist {
+        scanner = scanner.wordlist(words);
+    }
+    let scanner = scanner.build()?;
+    let reporter = Reporter::stdout(output);
     //trigger to start
     let scan_start = Instant::now();
-    //get all subdomain related code
-    let subdomains_modules = modules::all_subdomains_modules();
-    //concur
     runtime.block_on(async move {
-        // uses modules to enumarete subdomains
-        // ma
-        let mut subdomains: Vec<String> = stream::iter(subdomains_modules.into_iter())
-            .map(|module| async move {
-                match module.enumerate(target).await {
-                    Ok(new_subdomains) => Some(new_subdomains),
-                    Err(err) => {
-                        log::error!("subdomains/{}: {}", module.name(), err);
-                        None
-                    }
-                }
-            })
-            .buffer_unordered(subdomains_concur)
-            .filter_map(|domain| async { domain })
-            .collect::<Vec<Vec<String>>>()
-            .await
-            .into_iter()
-            .flatten()
-            .collect();
-        // ??maybe this puts the domain in the subdomains
-        subdomains.push(target.to_string());
-        // maps the domain to a Subdomain struct
-        // now we can deal with the ports
-        let subdomains: Vec<Subdomain> = HashSet::<String>::from_iter(subdomains.into_iter())
-            .into_iter()
-            .filter(|subdomain| subdomain.contains(target))
-            .map(|domain| Subdomain {
-                domain,
-                open_ports: Vec::new(),
-            })
-            .collect();
-        log::info!("Found {} domains", subdomains.len());
-        // do a dns lookup on the domains
-        // it serves as a check?
-        let subdomains: Vec<Subdomain> = stream::iter(subdomains.into_iter())
-            .map(|domain| dns::resolves(&dns_resolver, domain))
-            .buffer_unordered(dns_concur)
-            .filter_map(|domain| async move { domain })
-            .collect()
-            .await;
-        //gets the open ports
-        for subdomain in &subdomains {
-            println!("{}", subdomain.domain);
-            for port in &subdomain.open_ports {
-                println!("  {}", port.port);
-            }
-        }
-        println!("-----------------vuln---------------------");
-        let mut targets: Vec<(Box<dyn HttpModule>, String)> = Vec::new();
-        // tranforms subdomains/ports in a target & link it with an http module for exploitation
-        for subdomain in subdomains {
-            for port in subdomain.open_ports {
-                let http_modules = modules::all_http_modules();
-                for http_module in http_modules {
-                    let target = format!("http://{}:{:?}", &subdomain.domain, port);
-                    targets.push((http_module, target));
-                }
-            }
+        let mut events = scanner.scan();
+        while let Some(event) = events.next().await {
+            reporter.report(&event);
         }
-
-        // uses http modules to scan for vulns
-        // iter the targets testing the exploit modules
-        stream::iter(targets.into_iter())
-            .for_each_concurrent(vuln_concur, |(module, target)| {
-                let http_client = http_client.clone();
-                async move {
-                    match module.scan(&http_client, &target).await {
-                        Ok(Some(finding)) => println!("{:?}", &finding),
-                        Ok(None) => {}
-                        Err(err) => log::debug!("Error: {}", err),
-                    };
-                }
-            })
-            .await;
     });
     //how long it took
     let scan_duration = scan_start.elapsed();
     log::info!("scan completed in {:?}", scan_duration);
     Ok(())
 }
+
+// the schemes reqwest knows
+pub fn check_proxy(proxy: &str) -> Result<(), Error> {
+    let url = Url::parse(proxy).map_err(|_| Error::InvalidProxy(proxy.to_string()))?;
+    match url.scheme() {
+        "http" | "https" | "socks5" | "socks5h" if url.host().is_some() => Ok(()),
+        _ => Err(Error::InvalidProxy(proxy.to_string())),
+    }
+}
+
+// --header "Name: value", --cookie, --basic-auth user:pass and --bearer token
+// as the headers every request to the targets carries
+pub fn request_headers(
+    headers: &[&str],
+    cookie: Option<&str>,
+    basic_auth: Option<&str>,
+    bearer: Option<&str>,
+) -> Result<HeaderMap, Error> {
+    let invalid = |header: &str| Error::InvalidHeader(header.to_string());
+    let mut map = HeaderMap::new();
+    for header in headers {
+        let (name, value) = header.split_once(':').ok_or_else(|| invalid(header))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid(header))?;
+        let value = HeaderValue::from_str(value.trim()).map_err(|_| invalid(header))?;
+        map.append(name, value);
+    }
+    if let Some(cookie) = cookie {
+        let value = HeaderValue::from_str(cookie).map_err(|_| invalid(cookie))?;
+        map.insert(header::COOKIE, value);
+    }
+    let authorization = match (basic_auth, bearer) {
+        (Some(credentials), _) => {
+            if !credentials.contains(':') {
+                return Err(Error::InvalidHeader(String::from(
+                    "basic auth must be user:password",
+                )));
+            }
+            Some(format!("Basic {}", base64::encode(credentials)))
+        }
+        (None, Some(token)) => Some(format!("Bearer {}", token)),
+        (None, None) => None,
+    };
+    if let Some(authorization) = authorization {
+        let mut value = HeaderValue::from_str(&authorization)
+            .map_err(|_| Error::InvalidHeader(String::from("authorization")))?;
+        // keeps it out of debug logs
+        value.set_sensitive(true);
+        map.insert(header::AUTHORIZATION, value);
+    }
+    Ok(map)
+}
+
+// every client goes through the proxy when there is one
+fn client_builder(
+    proxy: &Option<String>,
+    user_agent: &Option<String>,
+    verify_tls: bool,
+) -> Result<ClientBuilder, Error> {
+    let mut builder = Client::builder().danger_accept_invalid_certs(!verify_tls);
+    if let Some(user_agent) = user_agent {
+        builder = builder.user_agent(user_agent.as_str());
+    }
+    match proxy {
+        Some(proxy) => Ok(builder.proxy(Proxy::all(proxy.as_str())?)),
+        None => Ok(builder),
+    }
+}
+
+// where the port scan connects go, only socks5 proxies can carry them
+fn socks_proxy(proxy: &Option<String>) -> Result<Option<SocketAddr>, Error> {
+    let url = match proxy {
+        Some(proxy) if proxy.starts_with("socks5") => proxy,
+        Some(_) => {
+            log::warn!("the port scan does not go through http proxies");
+            return Ok(None);
+        }
+        None => return Ok(None),
+    };
+    if !cfg!(feature = "socks") {
+        log::warn!("the port scan only goes through the proxy when built with the socks feature");
+        return Ok(None);
+    }
+    let url = Url::parse(url).map_err(|_| Error::InvalidProxy(url.to_string()))?;
+    let addr = url
+        .socket_addrs(|| Some(1080))
+        .ok()
+        .and_then(|addrs| addrs.into_iter().next())
+        .ok_or_else(|| Error::InvalidProxy(url.to_string()))?;
+    Ok(Some(addr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_proxy, client_builder, modules_info, request_headers};
+    use crate::{http_client::HttpClient, modules, testing::mock_server};
+    use std::collections::HashSet;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc,
+    };
+
+    #[test]
+    fn every_module_listed() {
+        let infos = modules_info(&[]).unwrap();
+        let names: HashSet<&str> = infos.iter().map(|info| info.name.as_str()).collect();
+        assert_eq!(infos.len(), names.len());
+        assert!(names.contains("subdomains/bruteforce"));
+        assert!(names.contains("dns/takeover"));
+        for info in infos.iter().filter(|info| info.kind == "http") {
+            assert!(info.severity.is_some(), "{}", info.name);
+            assert!(!info.requests.is_empty(), "{}", info.name);
+        }
+        let intrusive: Vec<&str> = infos
+            .iter()
+            .filter(|info| info.intrusive)
+            .map(|info| info.name.as_str())
+            .collect();
+        assert_eq!(vec!["http/cve_2018_7600"], intrusive);
+    }
+
+    #[test]
+    fn headers_from_options() {
+        let headers = request_headers(
+            &["X-Scanner: tricoder", "X-Team:red"],
+            Some("session=abc; theme=dark"),
+            Some("admin:hunter2"),
+            None,
+        )
+        .unwrap();
+        assert_eq!("tricoder", headers["x-scanner"]);
+        assert_eq!("red", headers["x-team"]);
+        assert_eq!("session=abc; theme=dark", headers["cookie"]);
+        assert_eq!("Basic YWRtaW46aHVudGVyMg==", headers["authorization"]);
+
+        let headers = request_headers(&[], None, None, Some("token")).unwrap();
+        assert_eq!("Bearer token", headers["authorization"]);
+
+        assert!(request_headers(&["no colon"], None, None, None).is_err());
+        assert!(request_headers(&["Bad Name: value"], None, None, None).is_err());
+        assert!(request_headers(&[], None, Some("nopassword"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn http_modules_send_headers() {
+        let (sender, mut requests) = mpsc::unbounded_channel();
+        let addr = mock_server(move |mut stream| {
+            let sender = sender.clone();
+            async move {
+                let mut buffer = [0; 4096];
+                let n = stream.read(&mut buffer).await.unwrap_or(0);
+                let _ = sender.send(String::from_utf8_lossy(&buffer[..n]).to_lowercase());
+                let _ = stream
+                    .write_all(b"HTTP/1.0 404 tricoder\r\nContent-Length: 0\r\n\r\n")
+                    .await;
+            }
+        })
+        .await;
+        let headers =
+            request_headers(&["X-Scanner: tricoder"], Some("session=abc"), None, None).unwrap();
+        let client: HttpClient = client_builder(&None, &Some(String::from("tricoder-test")), false)
+            .unwrap()
+            .default_headers(headers)
+            .build()
+            .unwrap()
+            .into();
+
+        let endpoint = format!("http://{}", addr);
+        for module in modules::all_http_modules() {
+            let _ = module.scan(&client, &endpoint).await;
+            let request = requests.recv().await.unwrap();
+            assert!(
+                request.contains("user-agent: tricoder-test"),
+                "{}",
+                module.name()
+            );
+            assert!(request.contains("x-scanner: tricoder"), "{}", module.name());
+            assert!(request.contains("cookie: session=abc"), "{}", module.name());
+            // drains the other requests of the module
+            while requests.try_recv().is_ok() {}
+        }
+    }
+
+    #[test]
+    fn proxies() {
+        assert!(check_proxy("socks5h://127.0.0.1:9050").is_ok());
+        assert!(check_proxy("http://proxy.internal:3128").is_ok());
+        assert!(check_proxy("ftp://proxy.internal").is_err());
+        assert!(check_proxy("127.0.0.1:9050").is_err());
+    }
+}
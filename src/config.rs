@@ -0,0 +1,491 @@
+use crate::{
+    cli::ScanOptions,
+    dns,
+    modules::{self, GitDump, ModuleFilter},
+    ports,
+    scanner::Concurrency,
+    takeover,
+    targets::Target,
+    throttle::Limits,
+    Error,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path, str::FromStr, sync::Arc, time::Duration};
+
+// shipped with the binary, see data/profiles.toml
+const PROFILES: &str = include_str!("../data/profiles.toml");
+
+// tricoder.toml looks like
+//
+//   profile = "stealth"      # used when there is no --profile
+//   [scan]                   # for every scan
+//   ports = "top1000"
+//   [profiles.lab]           # only with --profile lab
+//   resolvers = ["10.0.0.53"]
+//
+// the keys are the long flags of tricoder scan
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub profile: Option<String>,
+    pub scan: Settings,
+    pub profiles: HashMap<String, Settings>,
+}
+
+// one layer of the configuration, None is not set here
+// the same keys come from the cli, the env (TRICODER_PORTS_CONCURRENCY),
+// the config file and the defaults
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub ports: Option<String>,
+    // any, ipv4 or ipv6
+    pub ip_version: Option<String>,
+    pub service_detection: Option<bool>,
+    pub depth: Option<usize>,
+    pub wordlist: Option<String>,
+    pub resolvers: Option<Vec<String>>,
+    pub resolver_rate: Option<u32>,
+    pub takeover_fingerprints: Option<String>,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    pub headers: Option<Vec<String>>,
+    pub cookie: Option<String>,
+    pub basic_auth: Option<String>,
+    pub bearer: Option<String>,
+    // check the certificates of the targets, off since scanning by ip never matches
+    pub verify_tls: Option<bool>,
+    // seconds
+    pub subdomains_timeout: Option<u64>,
+    pub http_timeout: Option<u64>,
+    pub probe_timeout: Option<u64>,
+    pub subdomains_concurrency: Option<usize>,
+    pub dns_concurrency: Option<usize>,
+    pub ports_concurrency: Option<usize>,
+    pub http_concurrency: Option<usize>,
+    pub rate: Option<f64>,
+    pub host_rate: Option<f64>,
+    // milliseconds
+    pub jitter: Option<u64>,
+    // globs on the module names, no modules is all of them
+    pub modules: Option<Vec<String>>,
+    pub exclude_modules: Option<Vec<String>>,
+    // only the modules with one of these tags
+    pub tags: Option<Vec<String>>,
+    // the modules tagged intrusive only run with this
+    pub intrusive: Option<bool>,
+    // template files or directories of templates, see modules::Template
+    pub templates: Option<Vec<String>>,
+    // exposed .git repositories are rebuilt under this directory, see modules::GitDump
+    pub git_dump: Option<String>,
+    pub output: Option<String>,
+}
+
+impl Settings {
+    // what tricoder does when nobody says otherwise
+    pub fn defaults() -> Self {
+        Settings {
+            ports: Some(String::from("default")),
+            ip_version: Some(String::from("any")),
+            service_detection: Some(false),
+            depth: Some(1),
+            resolvers: Some(vec![String::from("quad9")]),
+            headers: Some(Vec::new()),
+            verify_tls: Some(false),
+            subdomains_timeout: Some(120),
+            http_timeout: Some(10),
+            probe_timeout: Some(3),
+            subdomains_concurrency: Some(20),
+            dns_concurrency: Some(100),
+            ports_concurrency: Some(200),
+            http_concurrency: Some(20),
+            jitter: Some(0),
+            modules: Some(Vec::new()),
+            exclude_modules: Some(Vec::new()),
+            tags: Some(Vec::new()),
+            intrusive: Some(false),
+            templates: Some(Vec::new()),
+            output: Some(String::from("text")),
+            ..Settings::default()
+        }
+    }
+
+    // TRICODER_<KEY>, lists are comma separated, headers one per line
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let var = |key: &str| var(&format!("TRICODER_{}", key));
+        Ok(Settings {
+            ports: var("PORTS"),
+            ip_version: var("IP_VERSION"),
+            service_detection: parse_env(&var, "SERVICE_DETECTION")?,
+            depth: parse_env(&var, "DEPTH")?,
+            wordlist: var("WORDLIST"),
+            resolvers: env_list(&var, "RESOLVERS", ','),
+            resolver_rate: parse_env(&var, "RESOLVER_RATE")?,
+            takeover_fingerprints: var("TAKEOVER_FINGERPRINTS"),
+            proxy: var("PROXY"),
+            user_agent: var("USER_AGENT"),
+            headers: env_list(&var, "HEADERS", '\n'),
+            cookie: var("COOKIE"),
+            basic_auth: var("BASIC_AUTH"),
+            bearer: var("BEARER"),
+            verify_tls: parse_env(&var, "VERIFY_TLS")?,
+            subdomains_timeout: parse_env(&var, "SUBDOMAINS_TIMEOUT")?,
+            http_timeout: parse_env(&var, "HTTP_TIMEOUT")?,
+            probe_timeout: parse_env(&var, "PROBE_TIMEOUT")?,
+            subdomains_concurrency: parse_env(&var, "SUBDOMAINS_CONCURRENCY")?,
+            dns_concurrency: parse_env(&var, "DNS_CONCURRENCY")?,
+            ports_concurrency: parse_env(&var, "PORTS_CONCURRENCY")?,
+            http_concurrency: parse_env(&var, "HTTP_CONCURRENCY")?,
+            rate: parse_env(&var, "RATE")?,
+            host_rate: parse_env(&var, "HOST_RATE")?,
+            jitter: parse_env(&var, "JITTER")?,
+            modules: env_list(&var, "MODULES", ','),
+            exclude_modules: env_list(&var, "EXCLUDE_MODULES", ','),
+            tags: env_list(&var, "TAGS", ','),
+            intrusive: parse_env(&var, "INTRUSIVE")?,
+            templates: env_list(&var, "TEMPLATES", ','),
+            git_dump: var("GIT_DUMP"),
+            output: var("OUTPUT"),
+        })
+    }
+
+    // the keys set here win, the others come from lower
+    pub fn or(self, lower: Settings) -> Self {
+        Settings {
+            ports: self.ports.or(lower.ports),
+            ip_version: self.ip_version.or(lower.ip_version),
+            service_detection: self.service_detection.or(lower.service_detection),
+            depth: self.depth.or(lower.depth),
+            wordlist: self.wordlist.or(lower.wordlist),
+            resolvers: self.resolvers.or(lower.resolvers),
+            resolver_rate: self.resolver_rate.or(lower.resolver_rate),
+            takeover_fingerprints: self.takeover_fingerprints.or(lower.takeover_fingerprints),
+            proxy: self.proxy.or(lower.proxy),
+            user_agent: self.user_agent.or(lower.user_agent),
+            headers: self.headers.or(lower.headers),
+            cookie: self.cookie.or(lower.cookie),
+            basic_auth: self.basic_auth.or(lower.basic_auth),
+            bearer: self.bearer.or(lower.bearer),
+            verify_tls: self.verify_tls.or(lower.verify_tls),
+            subdomains_timeout: self.subdomains_timeout.or(lower.subdomains_timeout),
+            http_timeout: self.http_timeout.or(lower.http_timeout),
+            probe_timeout: self.probe_timeout.or(lower.probe_timeout),
+            subdomains_concurrency: self.subdomains_concurrency.or(lower.subdomains_concurrency),
+            dns_concurrency: self.dns_concurrency.or(lower.dns_concurrency),
+            ports_concurrency: self.ports_concurrency.or(lower.ports_concurrency),
+            http_concurrency: self.http_concurrency.or(lower.http_concurrency),
+            rate: self.rate.or(lower.rate),
+            host_rate: self.host_rate.or(lower.host_rate),
+            jitter: self.jitter.or(lower.jitter),
+            modules: self.modules.or(lower.modules),
+            exclude_modules: self.exclude_modules.or(lower.exclude_modules),
+            tags: self.tags.or(lower.tags),
+            intrusive: self.intrusive.or(lower.intrusive),
+            templates: self.templates.or(lower.templates),
+            git_dump: self.git_dump.or(lower.git_dump),
+            output: self.output.or(lower.output),
+        }
+    }
+
+    // for config show, the credentials stay out of the terminal history
+    pub fn show(&self) -> String {
+        let mut shown = self.clone();
+        let hide = |value: &mut Option<String>| {
+            if value.is_some() {
+                *value = Some(String::from("***"));
+            }
+        };
+        hide(&mut shown.cookie);
+        hide(&mut shown.basic_auth);
+        hide(&mut shown.bearer);
+        toml::to_string(&shown).expect("config: printing the settings")
+    }
+}
+
+fn parse_env<T: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    key: &str,
+) -> Result<Option<T>, Error> {
+    var(key)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| Error::Config(format!("TRICODER_{}: invalid value {}", key, value)))
+        })
+        .transpose()
+}
+
+fn env_list(
+    var: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    separator: char,
+) -> Option<Vec<String>> {
+    var(key).map(|value| {
+        value
+            .split(separator)
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+pub fn builtin_profiles() -> HashMap<String, Settings> {
+    toml::from_str(PROFILES).expect("config: parsing the builtin profiles")
+}
+
+pub fn read_config(path: &Path) -> Result<ConfigFile, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Config(format!("{}: {}", path.display(), err)))?;
+    toml::from_str(&content).map_err(|err| Error::Config(format!("{}: {}", path.display(), err)))
+}
+
+// --config, then TRICODER_CONFIG, then ./tricoder.toml and ~/.config/tricoder/tricoder.toml
+// only an explicit path has to exist
+pub fn find_config(
+    path: Option<&str>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Result<Option<(String, ConfigFile)>, Error> {
+    if let Some(path) = path.map(str::to_string).or_else(|| var("TRICODER_CONFIG")) {
+        let config = read_config(Path::new(&path))?;
+        return Ok(Some((path, config)));
+    }
+    let mut candidates = vec![String::from("tricoder.toml")];
+    if let Some(home) = var("HOME") {
+        candidates.push(format!("{}/.config/tricoder/tricoder.toml", home));
+    }
+    for path in candidates {
+        if Path::new(&path).is_file() {
+            let config = read_config(Path::new(&path))?;
+            return Ok(Some((path, config)));
+        }
+    }
+    Ok(None)
+}
+
+// cli > env > profile > config file > defaults
+// the profile is --profile, then TRICODER_PROFILE, then the profile key of the file
+pub fn merge(
+    cli: Settings,
+    env: Settings,
+    config: ConfigFile,
+    profile: Option<String>,
+) -> Result<Settings, Error> {
+    let profile = match profile.or(config.profile) {
+        Some(name) => {
+            let mut profiles = builtin_profiles();
+            profiles.extend(config.profiles);
+            profiles
+                .remove(&name)
+                .ok_or_else(|| Error::Config(format!("unknown profile: {}", name)))?
+        }
+        None => Settings::default(),
+    };
+    Ok(cli
+        .or(env)
+        .or(profile)
+        .or(config.scan)
+        .or(Settings::defaults()))
+}
+
+// what cli::scan runs with
+// safe unwraps, the defaults have every key that has to be there
+pub fn scan_options(settings: Settings, targets: Vec<Target>) -> Result<ScanOptions, Error> {
+    let resolvers: Vec<&str> = settings
+        .resolvers
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let headers: Vec<&str> = settings
+        .headers
+        .as_ref()
+        .unwrap()
+        .iter()
+        .map(String::as_str)
+        .collect();
+    if let Some(proxy) = &settings.proxy {
+        crate::cli::check_proxy(proxy)?;
+    }
+    let mut http_modules = modules::http_modules(settings.templates.as_ref().unwrap())?;
+    if let Some(dir) = &settings.git_dump {
+        http_modules.push(Arc::new(GitDump::new(dir)));
+    }
+    Ok(ScanOptions {
+        targets,
+        ports: ports::parse_ports(settings.ports.as_ref().unwrap())?,
+        ip_version: settings.ip_version.as_ref().unwrap().parse()?,
+        service_detection: settings.service_detection.unwrap(),
+        max_depth: settings.depth.unwrap(),
+        wordlist: settings
+            .wordlist
+            .as_deref()
+            .map(modules::read_wordlist)
+            .transpose()?,
+        resolvers: dns::parse_resolvers(&resolvers)?,
+        resolver_rate: settings.resolver_rate,
+        takeover_fingerprints: match &settings.takeover_fingerprints {
+            Some(path) => takeover::read_fingerprints(path)?,
+            None => takeover::default_fingerprints(),
+        },
+        headers: crate::cli::request_headers(
+            &headers,
+            settings.cookie.as_deref(),
+            settings.basic_auth.as_deref(),
+            settings.bearer.as_deref(),
+        )?,
+        verify_tls: settings.verify_tls.unwrap(),
+        subdomains_timeout: Duration::from_secs(settings.subdomains_timeout.unwrap()),
+        http_timeout: Duration::from_secs(settings.http_timeout.unwrap()),
+        probe_timeout: Duration::from_secs(settings.probe_timeout.unwrap()),
+        concurrency: Concurrency {
+            subdomains: settings.subdomains_concurrency.unwrap(),
+            dns: settings.dns_concurrency.unwrap(),
+            ports: settings.ports_concurrency.unwrap(),
+            http: settings.http_concurrency.unwrap(),
+        },
+        limits: Limits {
+            rate: settings.rate,
+            host_rate: settings.host_rate,
+            jitter: Duration::from_millis(settings.jitter.unwrap()),
+        },
+        modules: ModuleFilter::new(
+            settings.modules.as_ref().unwrap(),
+            settings.exclude_modules.as_ref().unwrap(),
+            settings.tags.clone().unwrap(),
+            settings.intrusive.unwrap(),
+        )?,
+        http_modules,
+        output: settings.output.as_ref().unwrap().parse()?,
+        user_agent: settings.user_agent,
+        proxy: settings.proxy,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builtin_profiles, merge, scan_options, ConfigFile, Settings};
+    use crate::{dns::Resolver, modules};
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn builtin_profiles_parse() {
+        let profiles = builtin_profiles();
+        for name in ["stealth", "fast", "internal"] {
+            let settings = profiles[name].clone().or(Settings::defaults());
+            assert!(scan_options(settings, Vec::new()).is_ok(), "{}", name);
+        }
+        // a typo would silently keep the module on
+        let names: Vec<String> = modules::all_subdomains_modules(&Resolver::default())
+            .iter()
+            .map(|module| module.name())
+            .collect();
+        for name in profiles["internal"].exclude_modules.as_ref().unwrap() {
+            assert!(names.contains(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        let config: ConfigFile = toml::from_str(
+            r#"
+            profile = "stealth"
+            [scan]
+            rate = 50
+            http-concurrency = 7
+            ports = "top10"
+            depth = 3
+            [profiles.stealth]
+            rate = 2
+            ports = "top100"
+            "#,
+        )
+        .unwrap();
+        let cli = Settings {
+            ports: Some(String::from("80,443")),
+            ..Settings::default()
+        };
+        let env = Settings::from_env(env(&[
+            ("TRICODER_PORTS", "22"),
+            ("TRICODER_RATE", "3.5"),
+            ("TRICODER_RESOLVERS", "system, 1.1.1.1"),
+        ]))
+        .unwrap();
+
+        let settings = merge(cli, env, config, None).unwrap();
+        assert_eq!(Some(String::from("80,443")), settings.ports);
+        assert_eq!(Some(3.5), settings.rate);
+        // the profile of the file replaces the builtin one
+        assert_eq!(None, settings.host_rate);
+        assert_eq!(Some(7), settings.http_concurrency);
+        assert_eq!(Some(3), settings.depth);
+        assert_eq!(Some(200), settings.ports_concurrency);
+        assert_eq!(
+            Some(vec![String::from("system"), String::from("1.1.1.1")]),
+            settings.resolvers
+        );
+    }
+
+    #[test]
+    fn git_dump_only_when_asked() {
+        let has_git_dump = |settings: Settings| {
+            scan_options(settings, Vec::new())
+                .unwrap()
+                .http_modules
+                .iter()
+                .any(|module| module.name() == "http/git_dump")
+        };
+        assert!(!has_git_dump(Settings::defaults()));
+        let env = Settings::from_env(env(&[("TRICODER_GIT_DUMP", "dumps")])).unwrap();
+        assert!(has_git_dump(env.or(Settings::defaults())));
+    }
+
+    #[test]
+    fn builtin_profile_over_file() {
+        let config: ConfigFile = toml::from_str("[scan]\nhost-rate = 100").unwrap();
+        let settings = merge(
+            Settings::default(),
+            Settings::default(),
+            config,
+            Some(String::from("stealth")),
+        )
+        .unwrap();
+        assert_eq!(Some(1.0), settings.host_rate);
+
+        assert!(merge(
+            Settings::default(),
+            Settings::default(),
+            ConfigFile::default(),
+            Some(String::from("loud"))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert!(toml::from_str::<ConfigFile>("[scan]\nport = \"80\"").is_err());
+        assert!(toml::from_str::<ConfigFile>("[scan]\ndepth = \"deep\"").is_err());
+        assert!(Settings::from_env(env(&[("TRICODER_DEPTH", "deep")])).is_err());
+    }
+
+    #[test]
+    fn show_hides_credentials() {
+        let settings = Settings {
+            bearer: Some(String::from("secret-token")),
+            ..Settings::defaults()
+        };
+        let shown = settings.show();
+        assert!(shown.contains("bearer = \"***\""));
+        assert!(!shown.contains("secret-token"));
+        assert!(shown.contains("ports-concurrency = 200"));
+    }
+}
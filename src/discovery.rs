@@ -0,0 +1,148 @@
+use crate::{http_client::HttpClient, modules::Subdomain, targets::Target, Error};
+use regex::Regex;
+use reqwest::header::LOCATION;
+use std::{collections::HashSet, sync::OnceLock};
+
+// hostnames an http server points to: its redirect and the links of its page
+// the client must not follow redirects or we never see the Location
+pub async fn crawl(http_client: &HttpClient, url: &str) -> Result<Vec<String>, Error> {
+    let res = http_client.get(url).send().await?;
+    let mut names = Vec::new();
+    if let Some(location) = res.headers().get(LOCATION) {
+        names.extend(hostnames(&String::from_utf8_lossy(location.as_bytes())));
+    }
+    let body = res.text().await?;
+    names.extend(hostnames(&body));
+    Ok(names)
+}
+
+// every host of the absolute and protocol relative urls in the text
+pub fn hostnames(text: &str) -> Vec<String> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(r"(?i)(?:https?:)?//([a-z0-9_-]+(?:\.[a-z0-9_-]+)+)")
+            .expect("discovery: compiling url regexp")
+    });
+    regex
+        .captures_iter(text)
+        .map(|captures| captures[1].to_lowercase())
+        .collect()
+}
+
+// the sans of the certificates and the cnames of the subdomains
+pub fn scan_results_hostnames(subdomains: &[Subdomain]) -> Vec<String> {
+    let sans = subdomains
+        .iter()
+        .flat_map(|subdomain| &subdomain.open_ports)
+        .filter_map(|port| port.certificate.as_ref())
+        .flat_map(|certificate| certificate.sans.iter().cloned());
+    let cnames = subdomains
+        .iter()
+        .flat_map(|subdomain| subdomain.cnames.iter().cloned());
+    sans.chain(cnames).collect()
+}
+
+// the names that are in scope and not known yet
+pub fn new_domains(
+    names: Vec<String>,
+    targets: &[Target],
+    known_domains: &HashSet<String>,
+) -> Vec<String> {
+    let domains: HashSet<String> = names
+        .into_iter()
+        .map(|name| name.trim_end_matches('.').to_lowercase())
+        // wildcards cant be resolved
+        .filter(|name| !name.contains('*'))
+        .filter(|name| !known_domains.contains(name))
+        .filter(|name| targets.iter().any(|target| target.in_scope(name)))
+        .collect();
+    domains.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crawl, hostnames, new_domains, scan_results_hostnames};
+    use crate::{
+        http_client::HttpClient,
+        modules::{Port, Subdomain},
+        services::TlsCertificate,
+        targets::Target,
+        testing::http_server,
+    };
+    use reqwest::{redirect::Policy, Client};
+    use std::collections::HashSet;
+
+    #[test]
+    fn hostnames_in_text() {
+        let body = r#"<a href="https://API.kerkour.com/v1">api</a>
+            <script src="//cdn.kerkour.com/app.js"></script>
+            <img src="/static/logo.png"> mailto:contact@kerkour.com
+            <a href="http://10.0.0.1:8080/">lab</a>"#;
+
+        assert_eq!(
+            vec!["api.kerkour.com", "cdn.kerkour.com", "10.0.0.1"],
+            hostnames(body)
+        );
+    }
+
+    #[test]
+    fn new_domains_in_scope() {
+        let targets = vec![Target::Domain(String::from("kerkour.com"))];
+        let known = HashSet::from([String::from("www.kerkour.com")]);
+        let names = vec![
+            String::from("www.kerkour.com"),
+            String::from("API.kerkour.com."),
+            String::from("api.kerkour.com"),
+            String::from("*.kerkour.com"),
+            String::from("kerkour.fr"),
+        ];
+
+        assert_eq!(
+            vec![String::from("api.kerkour.com")],
+            new_domains(names, &targets, &known)
+        );
+    }
+
+    #[test]
+    fn sans_and_cnames() {
+        let mut www = Subdomain::new(String::from("www.kerkour.com"));
+        www.cnames = vec![String::from("kerkour.netlify.app")];
+        www.open_ports = vec![Port {
+            port: 443,
+            ip: "127.0.0.1".parse().unwrap(),
+            service: None,
+            certificate: Some(TlsCertificate {
+                subject: String::from("CN=kerkour.com"),
+                issuer: String::from("CN=R3"),
+                sans: vec![String::from("kerkour.com"), String::from("*.kerkour.com")],
+                not_before: None,
+                not_after: None,
+            }),
+            is_open: true,
+            findings: Vec::new(),
+        }];
+
+        assert_eq!(
+            vec!["kerkour.com", "*.kerkour.com", "kerkour.netlify.app"],
+            scan_results_hostnames(&[www])
+        );
+    }
+
+    #[tokio::test]
+    async fn crawl_redirect_and_links() {
+        let addr = http_server(vec![(
+            "/",
+            302,
+            vec![String::from("Location: https://login.kerkour.com/sso")],
+            br#"<a href="https://blog.kerkour.com/">blog</a>"#.to_vec(),
+        )])
+        .await;
+        let http_client =
+            HttpClient::from(Client::builder().redirect(Policy::none()).build().unwrap());
+
+        let names = crawl(&http_client, &format!("http://{}/", addr))
+            .await
+            .unwrap();
+        assert_eq!(vec!["login.kerkour.com", "blog.kerkour.com"], names);
+    }
+}
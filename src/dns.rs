@@ -1,26 +1,379 @@
-use crate::modules::Subdomain;
-use std::{sync::Arc, time::Duration};
-use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
+use crate::{modules::Subdomain, Error};
+use serde::Serialize;
+use std::{
+    fmt,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::time::{sleep_until, Instant};
+use trust_dns_resolver::config::{
+    NameServerConfig, NameServerConfigGroup, Protocol, ResolverConfig, ResolverOpts,
+};
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::lookup::{Lookup, NsLookup};
+use trust_dns_resolver::lookup_ip::LookupIp;
 use trust_dns_resolver::name_server::GenericConnection;
 use trust_dns_resolver::name_server::GenericConnectionProvider;
 use trust_dns_resolver::name_server::TokioRuntime;
-use trust_dns_resolver::AsyncResolver;
-pub type Resolver = Arc<AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>>;
+use trust_dns_resolver::proto::rr::{RData, RecordType};
+use trust_dns_resolver::proto::xfer::DnsRequestOptions;
+use trust_dns_resolver::{system_conf, AsyncResolver};
+
+type Upstream = AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>;
+
+// a record of the answer, the cname chain comes first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DnsRecord {
+    pub name: String,
+    // A, AAAA or CNAME
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub value: String,
+    pub ttl: u32,
+}
+
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} ({}s)",
+            self.name, self.record_type, self.value, self.ttl
+        )
+    }
+}
 
 //does a lookup
-pub async fn resolves(dns_resolver: &Resolver, domain: Subdomain) -> Option<Subdomain> {
-    if dns_resolver.lookup_ip(domain.domain.as_str()).await.is_ok() {
-        return Some(domain);
+// keeps the records so they end up in the report
+// a cname to nowhere is kept too, it can be a takeover
+pub async fn resolves(dns_resolver: &Resolver, mut domain: Subdomain) -> Option<Subdomain> {
+    match dns_resolver.lookup_ip(domain.domain.as_str()).await {
+        Ok(lookup) => {
+            domain.addresses = lookup.iter().collect();
+            domain.records = dns_records(lookup.as_lookup());
+        }
+        Err(_) => {
+            let lookup = dns_resolver
+                .lookup(domain.domain.as_str(), RecordType::CNAME)
+                .await
+                .ok()?;
+            domain.records = dns_records(&lookup);
+        }
+    }
+    domain.cnames = domain
+        .records
+        .iter()
+        .filter(|record| record.record_type == "CNAME")
+        .map(|record| record.value.clone())
+        .collect();
+    if domain.addresses.is_empty() && domain.cnames.is_empty() {
+        return None;
+    }
+    Some(domain)
+}
+
+fn dns_records(lookup: &Lookup) -> Vec<DnsRecord> {
+    lookup
+        .record_iter()
+        .filter_map(|record| {
+            let value = match record.data()? {
+                RData::A(ip) => ip.to_string(),
+                RData::AAAA(ip) => ip.to_string(),
+                RData::CNAME(name) => name.to_utf8().trim_end_matches('.').to_string(),
+                _ => return None,
+            };
+            Some(DnsRecord {
+                name: record.name().to_utf8().trim_end_matches('.').to_string(),
+                record_type: record.record_type().to_string(),
+                value,
+                ttl: record.ttl(),
+            })
+        })
+        .collect()
+}
+
+// every query goes to the next upstream in turn
+// so the load (and what each of them sees) is spread
+#[derive(Clone)]
+pub struct Resolver {
+    upstreams: Arc<Vec<(Upstream, Option<RateLimit>)>>,
+    next: Arc<AtomicUsize>,
+}
+
+impl Resolver {
+    pub async fn lookup_ip(&self, host: &str) -> Result<LookupIp, ResolveError> {
+        self.upstream().await.lookup_ip(host).await
+    }
+
+    pub async fn ns_lookup(&self, domain: &str) -> Result<NsLookup, ResolveError> {
+        self.upstream().await.ns_lookup(domain).await
+    }
+
+    pub async fn lookup(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> Result<Lookup, ResolveError> {
+        self.upstream()
+            .await
+            .lookup(name, record_type, DnsRequestOptions::default())
+            .await
+    }
+
+    // waits for the rate limit of the upstream whose turn it is
+    async fn upstream(&self) -> &Upstream {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.upstreams.len();
+        let (upstream, limit) = &self.upstreams[index];
+        if let Some(limit) = limit {
+            limit.wait().await;
+        }
+        upstream
+    }
+}
+
+// quad9 like it always was
+impl Default for Resolver {
+    fn default() -> Self {
+        new_resolver(vec![ResolverConfig::quad9()], None)
+    }
+}
+
+// spaces the queries sent to an upstream by 1/rate seconds
+struct RateLimit {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimit {
+    fn new(per_second: u32) -> Self {
+        RateLimit {
+            interval: Duration::from_secs(1) / per_second,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn wait(&self) {
+        let slot = {
+            let mut next = self.next.lock().expect("dns: locking rate limit");
+            let slot = (*next).max(Instant::now());
+            *next = slot + self.interval;
+            slot
+        };
+        sleep_until(slot).await;
     }
-    None
 }
+
 //creates an resolver
-// this has to be DOH to be secure/anon
-pub fn new_resolver() -> Resolver {
+// one upstream per config, rate is the max queries per second sent to each
+pub fn new_resolver(configs: Vec<ResolverConfig>, rate: Option<u32>) -> Resolver {
     let mut opts = ResolverOpts::default();
     opts.timeout = Duration::from_secs(4);
+    // keeps the cnames in the answer
+    opts.preserve_intermediates = true;
 
-    let mut resolver = AsyncResolver::tokio(ResolverConfig::quad9(), opts)
-        .expect("dns/new_resolver: building DNS client");
-    return Arc::new(resolver);
+    let upstreams = configs
+        .into_iter()
+        .map(|config| {
+            let upstream =
+                AsyncResolver::tokio(config, opts).expect("dns/new_resolver: building DNS client");
+            let limit = rate.filter(|rate| *rate > 0).map(RateLimit::new);
+            (upstream, limit)
+        })
+        .collect();
+    Resolver {
+        upstreams: Arc::new(upstreams),
+        next: Arc::new(AtomicUsize::new(0)),
+    }
+}
+
+// system, quad9, cloudflare, google
+// 10.0.0.2 or 10.0.0.2:5353 (udp and tcp)
+// tls://1.1.1.1#cloudflare-dns.com or tls://dns.quad9.net (DoT, port 853)
+// https://dns.google/dns-query or https://1.1.1.1#cloudflare-dns.com (DoH, port 443)
+pub fn parse_resolver(spec: &str) -> Result<ResolverConfig, Error> {
+    let spec = spec.trim();
+    let invalid = || Error::InvalidResolver(spec.to_string());
+
+    match spec {
+        "system" => {
+            return system_conf::read_system_conf()
+                .map(|(config, _)| config)
+                .map_err(|err| Error::InvalidResolver(format!("system: {}", err)))
+        }
+        "quad9" => return Ok(ResolverConfig::quad9()),
+        "cloudflare" => return Ok(ResolverConfig::cloudflare()),
+        "google" => return Ok(ResolverConfig::google()),
+        _ => {}
+    }
+
+    let (protocol, rest, default_port) = if let Some(rest) = spec.strip_prefix("tls://") {
+        (Protocol::Tls, rest, 853)
+    } else if let Some(rest) = spec.strip_prefix("https://") {
+        (Protocol::Https, rest, 443)
+    } else {
+        (Protocol::Udp, spec, 53)
+    };
+    let (rest, tls_name) = match rest.split_once('#') {
+        Some((rest, tls_name)) => (rest, Some(tls_name.to_string())),
+        None => (rest, None),
+    };
+    // the path of doh urls is always /dns-query for trust-dns
+    let host = rest.split('/').next().unwrap_or_default();
+    let addrs = socket_addrs(host, default_port).ok_or_else(invalid)?;
+
+    if protocol == Protocol::Udp {
+        let ips: Vec<IpAddr> = addrs.iter().map(SocketAddr::ip).collect();
+        let nameservers = NameServerConfigGroup::from_ips_clear(&ips, addrs[0].port(), true);
+        return Ok(ResolverConfig::from_parts(None, Vec::new(), nameservers));
+    }
+    // the certificate is checked against the name, an ip alone is not enough
+    let tls_name = match tls_name {
+        Some(tls_name) => tls_name,
+        None if host.parse::<IpAddr>().is_err() => host_name(host).to_string(),
+        None => return Err(invalid()),
+    };
+    let mut nameservers = NameServerConfigGroup::new();
+    for socket_addr in addrs {
+        nameservers.push(NameServerConfig {
+            socket_addr,
+            protocol,
+            tls_dns_name: Some(tls_name.clone()),
+            trust_nx_responses: true,
+            tls_config: None,
+            bind_addr: None,
+        });
+    }
+    Ok(ResolverConfig::from_parts(None, Vec::new(), nameservers))
+}
+
+pub fn parse_resolvers<S: AsRef<str>>(specs: &[S]) -> Result<Vec<ResolverConfig>, Error> {
+    specs
+        .iter()
+        .map(|spec| parse_resolver(spec.as_ref()))
+        .collect()
+}
+
+// ip, ip:port, [ipv6]:port, host or host:port
+// hosts are looked up once with the system resolver to bootstrap
+fn socket_addrs(host: &str, default_port: u16) -> Option<Vec<SocketAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Some(vec![SocketAddr::new(ip, default_port)]);
+    }
+    if let Ok(addr) = host.parse::<SocketAddr>() {
+        return Some(vec![addr]);
+    }
+    if host.is_empty() || host.contains('[') {
+        return None;
+    }
+    let with_port = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, default_port)
+    };
+    let addrs: Vec<SocketAddr> = with_port.to_socket_addrs().ok()?.collect();
+    if addrs.is_empty() {
+        None
+    } else {
+        Some(addrs)
+    }
+}
+
+fn host_name(host: &str) -> &str {
+    host.split(':').next().unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{new_resolver, parse_resolver, parse_resolvers};
+    use crate::testing::dns_server;
+    use std::{
+        collections::HashSet,
+        net::{IpAddr, SocketAddr},
+        time::Instant,
+    };
+    use trust_dns_resolver::config::Protocol;
+
+    fn nameservers(spec: &str) -> Vec<(SocketAddr, Protocol, Option<String>)> {
+        parse_resolver(spec)
+            .unwrap()
+            .name_servers()
+            .iter()
+            .map(|ns| (ns.socket_addr, ns.protocol, ns.tls_dns_name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_specs() {
+        assert_eq!(
+            vec![
+                ("10.0.0.2:53".parse().unwrap(), Protocol::Udp, None),
+                ("10.0.0.2:53".parse().unwrap(), Protocol::Tcp, None),
+            ],
+            nameservers("10.0.0.2")
+        );
+        assert_eq!(
+            "127.0.0.1:5353".parse::<SocketAddr>().unwrap(),
+            nameservers("127.0.0.1:5353")[0].0
+        );
+        assert_eq!(
+            vec![(
+                "1.1.1.1:853".parse().unwrap(),
+                Protocol::Tls,
+                Some(String::from("cloudflare-dns.com"))
+            )],
+            nameservers("tls://1.1.1.1#cloudflare-dns.com")
+        );
+        assert_eq!(
+            vec![(
+                "9.9.9.9:443".parse().unwrap(),
+                Protocol::Https,
+                Some(String::from("dns.quad9.net"))
+            )],
+            nameservers("https://9.9.9.9/dns-query#dns.quad9.net")
+        );
+        assert!(!nameservers("quad9").is_empty());
+
+        assert!(parse_resolver("tls://1.1.1.1").is_err());
+        assert!(parse_resolver("").is_err());
+        assert!(parse_resolvers(&["quad9", "[::1"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn round_robin() {
+        let first = dns_server(vec![("www.kerkour.com", "127.0.0.1")]).await;
+        let second = dns_server(vec![("www.kerkour.com", "127.0.0.2")]).await;
+        let dns_resolver = new_resolver(
+            parse_resolvers(&[first.to_string(), second.to_string()]).unwrap(),
+            None,
+        );
+
+        let mut answers = HashSet::new();
+        for _ in 0..2 {
+            let lookup = dns_resolver.lookup_ip("www.kerkour.com").await.unwrap();
+            answers.extend(lookup.iter());
+        }
+        let expected: HashSet<IpAddr> = ["127.0.0.1", "127.0.0.2"]
+            .iter()
+            .map(|ip| ip.parse().unwrap())
+            .collect();
+        assert_eq!(expected, answers);
+    }
+
+    #[tokio::test]
+    async fn rate_limit() {
+        let addr = dns_server(vec![("www.kerkour.com", "127.0.0.1")]).await;
+        let dns_resolver = new_resolver(parse_resolvers(&[addr.to_string()]).unwrap(), Some(10));
+
+        let start = Instant::now();
+        for label in ["a", "b", "c"] {
+            let _ = dns_resolver
+                .lookup_ip(&format!("{}.kerkour.com", label))
+                .await;
+        }
+        // the first query goes right away, the next two wait 100ms each
+        assert!(start.elapsed().as_millis() >= 200);
+    }
 }
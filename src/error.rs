@@ -10,6 +10,38 @@ pub enum Error {
     TokioJoinError(String),
     #[error("{0}: invalid http res")]
     InvalidHttpResponse(String),
+    #[error("unknown output format: {0}")]
+    InvalidOutputFormat(String),
+    #[error("invalid target: {0}")]
+    InvalidTarget(String),
+    #[error("reading targets file: {0}")]
+    TargetsFile(String),
+    #[error("invalid ports: {0}")]
+    InvalidPorts(String),
+    #[error("reading wordlist: {0}")]
+    Wordlist(String),
+    #[error("dns: {0}")]
+    Dns(String),
+    #[error("invalid resolver: {0}")]
+    InvalidResolver(String),
+    #[error("reading takeover fingerprints: {0}")]
+    Fingerprints(String),
+    #[error("invalid proxy: {0}")]
+    InvalidProxy(String),
+    #[error("invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("config: {0}")]
+    Config(String),
+    #[error("invalid module pattern: {0}")]
+    InvalidModulePattern(String),
+    #[error("unknown module: {0}, see tricoder modules")]
+    UnknownModule(String),
+    #[error("template: {0}")]
+    Template(String),
+    #[error("timed out after {0}")]
+    Timeout(String),
+    #[error("git: {0}")]
+    Git(String),
 }
 
 impl std::convert::From<reqwest::Error> for Error {
@@ -0,0 +1,459 @@
+// just enough of git's formats to rebuild a repository from the files of its .git:
+// loose objects, packs and their idx, the index, trees and commits
+use crate::Error;
+use flate2::read::ZlibDecoder;
+use std::{collections::HashMap, io::Read};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl Kind {
+    fn parse(name: &[u8]) -> Result<Self, Error> {
+        match name {
+            b"commit" => Ok(Kind::Commit),
+            b"tree" => Ok(Kind::Tree),
+            b"blob" => Ok(Kind::Blob),
+            b"tag" => Ok(Kind::Tag),
+            _ => Err(Error::Git(format!(
+                "unknown object type {}",
+                String::from_utf8_lossy(name)
+            ))),
+        }
+    }
+
+    // the type of a pack entry, deltas are not objects yet
+    fn from_pack(number: u8) -> Option<Self> {
+        match number {
+            1 => Some(Kind::Commit),
+            2 => Some(Kind::Tree),
+            3 => Some(Kind::Blob),
+            4 => Some(Kind::Tag),
+            _ => None,
+        }
+    }
+}
+
+pub fn is_sha(text: &str) -> bool {
+    text.len() == 40 && text.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    ZlibDecoder::new(data)
+        .read_to_end(&mut out)
+        .map_err(|err| Error::Git(format!("inflating: {}", err)))?;
+    Ok(out)
+}
+
+// objects/xx/yyyy is "<type> <size>\0<content>" deflated
+pub fn read_loose(raw: &[u8]) -> Result<(Kind, Vec<u8>), Error> {
+    let data = inflate(raw)?;
+    let nul = data
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or_else(|| Error::Git(String::from("loose object without header")))?;
+    let kind = data[..nul]
+        .split(|byte| *byte == b' ')
+        .next()
+        .unwrap_or(&[]);
+    Ok((Kind::parse(kind)?, data[nul + 1..].to_vec()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    pub mode: String,
+    pub name: String,
+    pub sha: String,
+}
+
+impl TreeEntry {
+    pub fn is_tree(&self) -> bool {
+        self.mode == "40000"
+    }
+
+    // submodules point to commits of another repository
+    pub fn is_submodule(&self) -> bool {
+        self.mode == "160000"
+    }
+}
+
+// "<mode> <name>\0<20 bytes sha>" again and again
+pub fn parse_tree(data: &[u8]) -> Result<Vec<TreeEntry>, Error> {
+    let mut entries = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let nul = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .filter(|nul| rest.len() >= nul + 21)
+            .ok_or_else(|| Error::Git(String::from("truncated tree")))?;
+        let header = String::from_utf8_lossy(&rest[..nul]);
+        let (mode, name) = header
+            .split_once(' ')
+            .ok_or_else(|| Error::Git(format!("invalid tree entry {}", header)))?;
+        entries.push(TreeEntry {
+            mode: mode.to_string(),
+            name: name.to_string(),
+            sha: hex(&rest[nul + 1..nul + 21]),
+        });
+        rest = &rest[nul + 21..];
+    }
+    Ok(entries)
+}
+
+// the objects a commit or a tag points to: tree and parents, or the tagged object
+pub fn references(kind: Kind, data: &[u8]) -> Vec<String> {
+    if !matches!(kind, Kind::Commit | Kind::Tag) {
+        return Vec::new();
+    }
+    String::from_utf8_lossy(data)
+        .lines()
+        // the headers end at the first empty line
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            matches!(key, "tree" | "parent" | "object").then(|| value.trim().to_string())
+        })
+        .filter(|sha| is_sha(sha))
+        .collect()
+}
+
+// the tree of a commit
+pub fn commit_tree(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .lines()
+        .next()?
+        .strip_prefix("tree ")
+        .map(|sha| sha.trim().to_string())
+        .filter(|sha| is_sha(sha))
+}
+
+// (path, sha) of the files in the index, versions 2 to 4
+pub fn parse_index(data: &[u8]) -> Result<Vec<(String, String)>, Error> {
+    let invalid = |what: &str| Error::Git(format!("index: {}", what));
+    if data.len() < 12 || &data[..4] != b"DIRC" {
+        return Err(invalid("not an index file"));
+    }
+    let version = u32::from_be_bytes(data[4..8].try_into().unwrap());
+    if !(2..=4).contains(&version) {
+        return Err(invalid(&format!("unsupported version {}", version)));
+    }
+    let count = u32::from_be_bytes(data[8..12].try_into().unwrap());
+
+    let mut entries = Vec::new();
+    let mut offset = 12;
+    let mut previous = String::new();
+    for _ in 0..count {
+        // ctime, mtime, dev, ino, mode, uid, gid, size, then the sha and the flags
+        let start = offset;
+        if data.len() < start + 62 {
+            return Err(invalid("truncated entry"));
+        }
+        let sha = hex(&data[start + 40..start + 60]);
+        let flags = u16::from_be_bytes([data[start + 60], data[start + 61]]);
+        offset = start + 62;
+        // extended flags
+        if version >= 3 && flags & 0x4000 != 0 {
+            offset += 2;
+        }
+        let path = if version == 4 {
+            // the path drops the end of the previous one and adds the rest
+            let (strip, read) = varint(data.get(offset..).unwrap_or_default())
+                .ok_or_else(|| invalid("truncated entry"))?;
+            offset += read;
+            let len = data[offset.min(data.len())..]
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| invalid("truncated entry"))?;
+            let keep = previous.len().saturating_sub(strip as usize);
+            let mut path = previous.get(..keep).unwrap_or_default().to_string();
+            path.push_str(&String::from_utf8_lossy(&data[offset..offset + len]));
+            offset += len + 1;
+            path
+        } else {
+            let len = data[offset.min(data.len())..]
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| invalid("truncated entry"))?;
+            let path = String::from_utf8_lossy(&data[offset..offset + len]).to_string();
+            // entries are padded with nuls to a multiple of 8 bytes
+            offset += (offset - start + len + 8) / 8 * 8 - (offset - start);
+            path
+        };
+        previous = path.clone();
+        entries.push((path, sha));
+    }
+    Ok(entries)
+}
+
+// the offset varint of the index v4 and of ofs deltas
+// 7 bits a byte, high bit set when more follow, +1 at each continuation
+fn varint(data: &[u8]) -> Option<(u64, usize)> {
+    let mut byte = *data.first()?;
+    let mut value = (byte & 0x7f) as u64;
+    let mut read = 1;
+    while byte & 0x80 != 0 {
+        byte = *data.get(read)?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as u64;
+        read += 1;
+    }
+    Some((value, read))
+}
+
+// a pack with its idx, objects are read on demand
+pub struct Pack {
+    offsets: HashMap<String, usize>,
+    data: Vec<u8>,
+}
+
+// deltas of deltas of ... git stops at 50 by default
+const MAX_DELTA_DEPTH: usize = 64;
+
+impl Pack {
+    // only version 2 idx files, git writes them since 1.5.2
+    pub fn parse(idx: &[u8], data: Vec<u8>) -> Result<Self, Error> {
+        let invalid = |what: &str| Error::Git(format!("pack: {}", what));
+        if idx.len() < 8 + 256 * 4 || idx[..8] != [0xff, b't', b'O', b'c', 0, 0, 0, 2] {
+            return Err(invalid("unsupported idx file"));
+        }
+        if data.len() < 12 || &data[..4] != b"PACK" {
+            return Err(invalid("not a pack file"));
+        }
+        let u32_at = |at: usize| -> Result<u32, Error> {
+            idx.get(at..at + 4)
+                .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+                .ok_or_else(|| invalid("truncated idx file"))
+        };
+        let count = u32_at(8 + 255 * 4)? as usize;
+        let shas = 8 + 256 * 4;
+        let offsets_at = shas + count * 20 + count * 4;
+        let large_offsets_at = offsets_at + count * 4;
+
+        let mut offsets = HashMap::with_capacity(count);
+        for i in 0..count {
+            let sha = idx
+                .get(shas + i * 20..shas + (i + 1) * 20)
+                .ok_or_else(|| invalid("truncated idx file"))?;
+            let offset = u32_at(offsets_at + i * 4)?;
+            // the high bit says it is in the table of 8 bytes offsets
+            let offset = if offset & 0x8000_0000 != 0 {
+                let at = large_offsets_at + (offset & 0x7fff_ffff) as usize * 8;
+                idx.get(at..at + 8)
+                    .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()) as usize)
+                    .ok_or_else(|| invalid("truncated idx file"))?
+            } else {
+                offset as usize
+            };
+            offsets.insert(hex(sha), offset);
+        }
+        Ok(Pack { offsets, data })
+    }
+
+    pub fn shas(&self) -> impl Iterator<Item = &String> {
+        self.offsets.keys()
+    }
+
+    pub fn object(&self, sha: &str) -> Result<Option<(Kind, Vec<u8>)>, Error> {
+        match self.offsets.get(sha) {
+            Some(offset) => self.object_at(*offset, 0).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn object_at(&self, offset: usize, depth: usize) -> Result<(Kind, Vec<u8>), Error> {
+        let invalid = |what: &str| Error::Git(format!("pack: {}", what));
+        if depth > MAX_DELTA_DEPTH {
+            return Err(invalid("delta chain too long"));
+        }
+        // type in bits 4-6 of the first byte, then the size we do not need
+        let mut at = offset;
+        let mut byte = *self.data.get(at).ok_or_else(|| invalid("truncated"))?;
+        let number = (byte >> 4) & 7;
+        at += 1;
+        while byte & 0x80 != 0 {
+            byte = *self.data.get(at).ok_or_else(|| invalid("truncated"))?;
+            at += 1;
+        }
+        match number {
+            // ofs delta, the base is that many bytes before
+            6 => {
+                let (distance, read) =
+                    varint(&self.data[at..]).ok_or_else(|| invalid("truncated"))?;
+                let base = offset
+                    .checked_sub(distance as usize)
+                    .ok_or_else(|| invalid("delta base out of the pack"))?;
+                let (kind, base) = self.object_at(base, depth + 1)?;
+                Ok((
+                    kind,
+                    apply_delta(&base, &inflate(&self.data[at + read..])?)?,
+                ))
+            }
+            // ref delta, the base is named by its sha
+            7 => {
+                let sha = hex(self
+                    .data
+                    .get(at..at + 20)
+                    .ok_or_else(|| invalid("truncated"))?);
+                let base = *self
+                    .offsets
+                    .get(&sha)
+                    .ok_or_else(|| invalid(&format!("delta base {} not in the pack", sha)))?;
+                let (kind, base) = self.object_at(base, depth + 1)?;
+                Ok((kind, apply_delta(&base, &inflate(&self.data[at + 20..])?)?))
+            }
+            _ => {
+                let kind = Kind::from_pack(number)
+                    .ok_or_else(|| invalid(&format!("unknown entry type {}", number)))?;
+                Ok((kind, inflate(&self.data[at..])?))
+            }
+        }
+    }
+}
+
+// sizes of a delta, 7 bits a byte little endian without the +1 of varint
+fn delta_size(data: &[u8], at: &mut usize) -> Option<usize> {
+    let mut size = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*at)?;
+        *at += 1;
+        size |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(size);
+        }
+    }
+}
+
+// a delta is the sizes of the base and the result, then copies from the base
+// and inserts of new bytes
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>, Error> {
+    let invalid = || Error::Git(String::from("pack: invalid delta"));
+    let mut at = 0;
+    let base_size = delta_size(delta, &mut at).ok_or_else(invalid)?;
+    let size = delta_size(delta, &mut at).ok_or_else(invalid)?;
+    if base_size != base.len() {
+        return Err(invalid());
+    }
+    let mut out = Vec::with_capacity(size);
+    while at < delta.len() {
+        let op = delta[at];
+        at += 1;
+        if op & 0x80 != 0 {
+            // which of the 4 offset and 3 size bytes follow
+            let mut fields = [0usize; 7];
+            for (bit, field) in fields.iter_mut().enumerate() {
+                if op & (1 << bit) != 0 {
+                    *field = *delta.get(at).ok_or_else(invalid)? as usize;
+                    at += 1;
+                }
+            }
+            let copy_offset = fields[0] | fields[1] << 8 | fields[2] << 16 | fields[3] << 24;
+            let copy_size = match fields[4] | fields[5] << 8 | fields[6] << 16 {
+                0 => 0x10000,
+                copy_size => copy_size,
+            };
+            let copied = base
+                .get(copy_offset..copy_offset + copy_size)
+                .ok_or_else(invalid)?;
+            out.extend_from_slice(copied);
+        } else if op != 0 {
+            let inserted = delta.get(at..at + op as usize).ok_or_else(invalid)?;
+            out.extend_from_slice(inserted);
+            at += op as usize;
+        } else {
+            return Err(invalid());
+        }
+    }
+    if out.len() != size {
+        return Err(invalid());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_delta, parse_index, parse_tree, read_loose, references, Kind};
+    use flate2::{write::ZlibEncoder, Compression};
+    use std::io::Write;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn loose_objects() {
+        let (kind, data) = read_loose(&deflate(b"blob 6\0hello\n")).unwrap();
+        assert_eq!(Kind::Blob, kind);
+        assert_eq!(b"hello\n".to_vec(), data);
+        assert!(read_loose(&deflate(b"nope 1\0a")).is_err());
+        assert!(read_loose(b"not deflated").is_err());
+    }
+
+    #[test]
+    fn trees_and_commits() {
+        let mut tree = b"100644 README.md\0".to_vec();
+        tree.extend_from_slice(&[0xab; 20]);
+        tree.extend_from_slice(b"40000 src\0");
+        tree.extend_from_slice(&[0x01; 20]);
+        let entries = parse_tree(&tree).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!("README.md", entries[0].name);
+        assert_eq!("ab".repeat(20), entries[0].sha);
+        assert!(entries[1].is_tree());
+        assert!(parse_tree(&tree[..30]).is_err());
+
+        let tree_sha = "1".repeat(40);
+        let parent = "2".repeat(40);
+        let commit = format!(
+            "tree {}\nparent {}\nauthor a <a@a> 0 +0000\n\nparent {} in the message\n",
+            tree_sha,
+            parent,
+            "3".repeat(40)
+        );
+        assert_eq!(
+            vec![tree_sha, parent],
+            references(Kind::Commit, commit.as_bytes())
+        );
+        assert!(references(Kind::Blob, commit.as_bytes()).is_empty());
+    }
+
+    #[test]
+    fn deltas() {
+        let base = b"hello world, this is the base";
+        // sizes, copy 6 bytes from offset 0, insert "there"
+        let mut delta = vec![base.len() as u8, 11, 0x80 | 0x10, 6, 5];
+        delta.extend_from_slice(b"there");
+        assert_eq!(b"hello there".to_vec(), apply_delta(base, &delta).unwrap());
+        // wrong base size
+        delta[0] = 3;
+        assert!(apply_delta(base, &delta).is_err());
+    }
+
+    #[test]
+    fn index_v2() {
+        let mut index = b"DIRC\0\0\0\x02\0\0\0\x01".to_vec();
+        index.extend_from_slice(&[0; 40]);
+        index.extend_from_slice(&[0xcd; 20]);
+        index.extend_from_slice(&[0, 6]);
+        index.extend_from_slice(b"app.py");
+        // 68 bytes padded to 72 with nuls
+        index.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            vec![(String::from("app.py"), "cd".repeat(20))],
+            parse_index(&index).unwrap()
+        );
+        assert!(parse_index(b"DIRC\0\0\0\x09\0\0\0\0").is_err());
+    }
+}
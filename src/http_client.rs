@@ -0,0 +1,237 @@
+use crate::{
+    retry::{self, Backoff},
+    throttle::Throttle,
+    Error,
+};
+use reqwest::{header::HeaderName, Client, Method, RequestBuilder, Response};
+use serde::Serialize;
+use std::sync::Arc;
+use url::Url;
+
+// every request of the modules goes through here so the rate limits,
+// the jitter and the back-off apply to all of them
+#[derive(Clone)]
+pub struct HttpClient {
+    client: Client,
+    throttle: Arc<Throttle>,
+    backoff: Backoff,
+    // 429/503 are always retried, the other 5xx only when this is set
+    // a 500 from a target is usually what a module probes for, not a hiccup
+    retry_server_errors: bool,
+}
+
+impl HttpClient {
+    pub fn new(client: Client, throttle: Arc<Throttle>) -> Self {
+        HttpClient {
+            client,
+            throttle,
+            backoff: Backoff::default(),
+            retry_server_errors: false,
+        }
+    }
+
+    // for the third party apis
+    pub fn retry_server_errors(mut self) -> Self {
+        self.retry_server_errors = true;
+        self
+    }
+
+    pub fn get(&self, url: &str) -> Request<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub fn post(&self, url: &str) -> Request<'_> {
+        self.request(Method::POST, url)
+    }
+
+    pub fn request(&self, method: Method, url: &str) -> Request<'_> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+        Request {
+            client: self,
+            builder: self.client.request(method, url),
+            host,
+        }
+    }
+}
+
+// no limits, mostly for the tests
+impl From<Client> for HttpClient {
+    fn from(client: Client) -> Self {
+        HttpClient::new(client, Arc::new(Throttle::unlimited()))
+    }
+}
+
+// the few parts of reqwest's RequestBuilder the modules use
+pub struct Request<'a> {
+    client: &'a HttpClient,
+    builder: RequestBuilder,
+    // the throttle key
+    host: String,
+}
+
+impl Request<'_> {
+    pub fn header(mut self, name: HeaderName, value: &str) -> Self {
+        self.builder = self.builder.header(name, value);
+        self
+    }
+
+    pub fn query<T: Serialize + ?Sized>(mut self, query: &T) -> Self {
+        self.builder = self.builder.query(query);
+        self
+    }
+
+    pub fn form<T: Serialize + ?Sized>(mut self, form: &T) -> Self {
+        self.builder = self.builder.form(form);
+        self
+    }
+
+    pub fn body(mut self, body: String) -> Self {
+        self.builder = self.builder.body(body);
+        self
+    }
+
+    // waits for its turn, sends and retries while the server is overloaded
+    // a 429/503 pauses every request to the host, not just this one
+    // the last response is returned as is
+    pub async fn send(self) -> Result<Response, Error> {
+        let client = self.client;
+        let mut attempt = 0;
+        loop {
+            let request = self
+                .builder
+                .try_clone()
+                .ok_or_else(|| Error::Reqwest(String::from("request can not be retried")))?;
+            client.throttle.wait(&self.host).await;
+            let res = request.send().await?;
+            let status = res.status();
+            let overloaded = retry::overloaded(status);
+            let again = overloaded || (client.retry_server_errors && retry::should_retry(status));
+            if !again {
+                return Ok(res);
+            }
+            let delay = client.backoff.delay(attempt, &res);
+            if overloaded {
+                client.throttle.back_off(&self.host, delay);
+            }
+            if attempt >= client.backoff.retries {
+                return Ok(res);
+            }
+            log::debug!("{}: {}, retrying in {:?}", res.url(), status, delay);
+            // the throttle already holds overloaded hosts back
+            if !overloaded {
+                tokio::time::sleep(delay).await;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HttpClient;
+    use crate::{retry::Backoff, testing::mock_server};
+    use reqwest::{Client, StatusCode};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        time::Instant,
+    };
+
+    // answers with the statuses in order, the last one forever
+    async fn flaky_server(statuses: &'static [u16]) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let addr = mock_server(move |mut stream| {
+            let counter = counter.clone();
+            async move {
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer).await;
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[n.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.0 {} tricoder\r\nContent-Length: 2\r\n\r\nok",
+                    status
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        })
+        .await;
+        (format!("http://{}/", addr), requests)
+    }
+
+    fn http_client() -> HttpClient {
+        let mut http_client = HttpClient::from(Client::new());
+        http_client.backoff = Backoff {
+            retries: 3,
+            base: Duration::from_millis(10),
+            max: Duration::from_millis(50),
+        };
+        http_client
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let (url, requests) = flaky_server(&[429, 503, 200]).await;
+        let res = http_client().get(&url).send().await.unwrap();
+
+        assert_eq!(StatusCode::OK, res.status());
+        assert_eq!(3, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn server_errors_only_retried_when_asked() {
+        let (url, requests) = flaky_server(&[500]).await;
+        let res = http_client().get(&url).send().await.unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.status());
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+
+        let res = http_client()
+            .retry_server_errors()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, res.status());
+        assert_eq!(5, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn no_retry_on_client_errors() {
+        let (url, requests) = flaky_server(&[404]).await;
+        let res = http_client()
+            .retry_server_errors()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(StatusCode::NOT_FOUND, res.status());
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn overloaded_host_is_paused() {
+        let (url, _) = flaky_server(&[429, 200]).await;
+        let mut http_client = http_client();
+        http_client.backoff.retries = 0;
+        http_client.backoff.base = Duration::from_millis(200);
+        http_client.backoff.max = Duration::from_millis(200);
+
+        let res = http_client.get(&url).send().await.unwrap();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, res.status());
+        // the next request to the host waits out the back-off
+        let start = Instant::now();
+        let res = http_client.get(&url).send().await.unwrap();
+        assert_eq!(StatusCode::OK, res.status());
+        assert!(start.elapsed() >= Duration::from_millis(190));
+    }
+}
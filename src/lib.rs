@@ -0,0 +1,28 @@
+// tricoder as a library, src/main.rs is the command line on top of it
+// crates linking it can add modules with register_http_module! and
+// register_subdomains_module!, see modules/registry.rs
+pub mod cli;
+mod common_ports;
+pub mod config;
+mod discovery;
+pub mod dns;
+pub mod error;
+mod git;
+pub mod http_client;
+pub mod modules;
+pub mod ports;
+pub mod report;
+mod retry;
+pub mod scanner;
+pub mod services;
+pub mod takeover;
+pub mod targets;
+#[cfg(test)]
+mod testing;
+pub mod throttle;
+pub use error::Error;
+pub use scanner::{Event, Scanner, ScannerBuilder};
+
+// for the registration macros, the crates using them do not need it
+#[doc(hidden)]
+pub use inventory;
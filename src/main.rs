@@ -1,14 +1,12 @@
 use anyhow::Result;
-use clap::{Arg, Command};
-use std::env;
+use clap::{Arg, ArgMatches, Command};
+use std::{env, fmt::Display, str::FromStr};
 
-mod cli;
-mod common_ports;
-mod dns;
-mod error;
-mod modules;
-mod ports;
-pub use error::Error;
+use tricoder::{
+    cli,
+    config::{self, ConfigFile, Settings},
+    targets,
+};
 
 fn main() -> Result<()> {
     env::set_var("RUST LOG", "info,trust_dns_proto=error");
@@ -16,25 +14,346 @@ fn main() -> Result<()> {
     let cli = Command::new(clap::crate_name!())
         .version(clap::crate_version!())
         .about(clap::crate_description!())
-        .subcommand(Command::new("modules").about("list all modules"))
+        .arg(
+            Arg::new("config")
+                .help("config file, by default ./tricoder.toml or ~/.config/tricoder/tricoder.toml")
+                .long("config")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::new("profile")
+                .help("profile of the config file or a builtin one: stealth, fast, internal")
+                .long("profile")
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            Command::new("modules")
+                .about("list all modules")
+                .arg(
+                    Arg::new("format")
+                        .help("how to print them")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(["text", "json"])
+                        .default_value("text")
+                        .global(true),
+                )
+                .arg(templates_arg().global(true))
+                .subcommand(
+                    Command::new("info")
+                        .about("what a module checks and the requests it sends")
+                        .arg(Arg::new("name").help("name of the module").required(true)),
+                ),
+        )
         .subcommand(
-            Command::new("scan").about("scan target").arg(
-                Arg::new("target")
-                    .help("the domain name")
-                    .required(true)
-                    .index(1),
-            ),
+            Command::new("scan")
+                .about("scan target")
+                .arg(
+                    Arg::new("target")
+                        .help("domains, ips, cidr ranges or urls to scan")
+                        .multiple_values(true)
+                        .required_unless_present("targets-file")
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("targets-file")
+                        .help("file with one target per line")
+                        .long("targets-file")
+                        .short('f')
+                        .takes_value(true),
+                )
+                .args(settings_args()),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("configuration of the scans")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("show")
+                        .about("print the configuration a scan would run with: flags, env, config file and defaults merged")
+                        .args(settings_args()),
+                ),
         )
         .arg_required_else_help(true)
         .get_matches();
 
-    if let Some(_) = cli.subcommand_matches("modules") {
-        cli::modules();
+    if let Some(matches) = cli.subcommand_matches("modules") {
+        let info = matches.subcommand_matches("info");
+        let matches = info.unwrap_or(matches);
+        // the templates of the config file are listed too
+        let cli_templates = Settings {
+            templates: matches
+                .values_of("templates")
+                .map(|values| values.map(|value| value.to_string()).collect()),
+            ..Settings::default()
+        };
+        let (_, settings) = settings(matches, cli_templates)?;
+        // safe unwraps bcs args are required or have a default
+        let templates = settings.templates.unwrap();
+        let json = matches.value_of("format").unwrap() == "json";
+        match info {
+            Some(matches) => cli::module_info(matches.value_of("name").unwrap(), json, &templates)?,
+            None => cli::modules(json, &templates)?,
+        }
     } else if let Some(matches) = cli.subcommand_matches("scan") {
-        //safe unwrap bcs arg is required
-        let target = matches.value_of("target").unwrap();
-        cli::scan(target)?;
+        let mut inputs: Vec<String> = matches
+            .values_of("target")
+            .map(|values| values.map(|value| value.to_string()).collect())
+            .unwrap_or_default();
+        if let Some(path) = matches.value_of("targets-file") {
+            inputs.extend(targets::read_file(path)?);
+        }
+        let targets = targets::parse_all(&inputs)?;
+        let (_, settings) = settings(matches, cli_settings(matches)?)?;
+        cli::scan(config::scan_options(settings, targets)?)?;
+    } else if let Some(matches) = cli.subcommand_matches("config") {
+        if let Some(matches) = matches.subcommand_matches("show") {
+            let (path, settings) = settings(matches, cli_settings(matches)?)?;
+            println!("# config file: {}", path.as_deref().unwrap_or("none"));
+            print!("{}", settings.show());
+        }
     }
 
     Ok(())
 }
+
+// the flags that are also config keys
+// no default values here, they would hide the env and the config file
+// see Settings::defaults
+fn settings_args() -> Vec<Arg<'static>> {
+    vec![
+        Arg::new("ports")
+            .help("ports to scan: 80,443,8000-8100,top100,top1000,all,default (default: default)")
+            .long("ports")
+            .short('p')
+            .takes_value(true),
+        Arg::new("ipv4")
+            .help("only scan the ipv4 addresses of the targets")
+            .long("ipv4")
+            .short('4')
+            .conflicts_with("ipv6"),
+        Arg::new("ipv6")
+            .help("only scan the ipv6 addresses of the targets")
+            .long("ipv6")
+            .short('6'),
+        Arg::new("service-detection")
+            .help("probe open ports to find what runs on them, http modules then only run on http(s) ports")
+            .long("service-detection")
+            .short('s'),
+        Arg::new("wordlist")
+            .help("resolve every word of this file as a subdomain of the domain targets")
+            .long("wordlist")
+            .short('w')
+            .takes_value(true),
+        Arg::new("resolver")
+            .help("dns servers to use in turn: system, quad9, cloudflare, google, ip[:port], tls://ip#name, https://host/dns-query (default: quad9)")
+            .long("resolver")
+            .short('r')
+            .takes_value(true)
+            .multiple_occurrences(true),
+        Arg::new("resolver-rate")
+            .help("max dns queries per second sent to each resolver")
+            .long("resolver-rate")
+            .takes_value(true),
+        Arg::new("takeover-fingerprints")
+            .help("json file with the services checked for subdomain takeovers, replaces the default list")
+            .long("takeover-fingerprints")
+            .takes_value(true),
+        Arg::new("proxy")
+            .help("send the http requests through this proxy: http://, https://, socks5:// or socks5h:// (tor)")
+            .long("proxy")
+            .takes_value(true)
+            .validator(cli::check_proxy),
+        Arg::new("subdomains-timeout")
+            .help("seconds a subdomain module gets for a domain before its results are dropped (default: 120)")
+            .long("subdomains-timeout")
+            .takes_value(true),
+        Arg::new("http-timeout")
+            .help("seconds an http request to a target gets (default: 10)")
+            .long("http-timeout")
+            .takes_value(true),
+        Arg::new("probe-timeout")
+            .help("seconds a service or tls probe of an open port gets (default: 3)")
+            .long("probe-timeout")
+            .takes_value(true),
+        Arg::new("verify-tls")
+            .help("refuse targets with invalid certificates")
+            .long("verify-tls"),
+        Arg::new("user-agent")
+            .help("user agent of every http request")
+            .long("user-agent")
+            .takes_value(true),
+        Arg::new("header")
+            .help("\"Name: value\" header added to every request to the targets, can be repeated")
+            .long("header")
+            .short('H')
+            .takes_value(true)
+            .multiple_occurrences(true),
+        Arg::new("cookie")
+            .help("cookies sent to the targets: \"session=abc; other=def\"")
+            .long("cookie")
+            .takes_value(true),
+        Arg::new("basic-auth")
+            .help("user:password for http basic auth on the targets")
+            .long("basic-auth")
+            .takes_value(true)
+            .conflicts_with("bearer"),
+        Arg::new("bearer")
+            .help("bearer token sent to the targets")
+            .long("bearer")
+            .takes_value(true),
+        Arg::new("depth")
+            .help("how many times hostnames found while scanning (tls, cnames, redirects, links) are scanned in turn (default: 1)")
+            .long("depth")
+            .takes_value(true),
+        Arg::new("subdomains-concurrency")
+            .help("subdomain modules running at the same time (default: 20)")
+            .long("subdomains-concurrency")
+            .takes_value(true),
+        Arg::new("dns-concurrency")
+            .help("dns queries in flight at the same time (default: 100)")
+            .long("dns-concurrency")
+            .takes_value(true),
+        Arg::new("ports-concurrency")
+            .help("connections at the same time to each scanned ip (default: 200)")
+            .long("ports-concurrency")
+            .takes_value(true),
+        Arg::new("http-concurrency")
+            .help("http modules running at the same time (default: 20)")
+            .long("http-concurrency")
+            .takes_value(true),
+        Arg::new("rate")
+            .help("max requests per second, http requests and port connects to all hosts together")
+            .long("rate")
+            .takes_value(true),
+        Arg::new("host-rate")
+            .help("max requests per second to one host, it is paused on 429 and 503 answers anyway")
+            .long("host-rate")
+            .takes_value(true),
+        Arg::new("jitter")
+            .help("wait a random time up to this many milliseconds before every request")
+            .long("jitter")
+            .takes_value(true),
+        Arg::new("modules")
+            .help("only run the modules matching these globs: http/git_*,subdomains/crtsh")
+            .long("modules")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .use_value_delimiter(true),
+        Arg::new("exclude-modules")
+            .help("do not run the modules matching these globs")
+            .long("exclude-modules")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .use_value_delimiter(true),
+        Arg::new("tags")
+            .help("only run the modules with one of these tags: git, cve, disclosure, unauthenticated, misconfiguration, dns, passive")
+            .long("tags")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .use_value_delimiter(true),
+        Arg::new("intrusive")
+            .help("also run the intrusive modules, they send payloads to the targets")
+            .long("intrusive"),
+        templates_arg(),
+        Arg::new("git-dump")
+            .help("rebuild the exposed .git repositories in this directory and look for secrets in them")
+            .long("git-dump")
+            .takes_value(true),
+        Arg::new("output")
+            .help("how to print the results (default: text)")
+            .long("output")
+            .short('o')
+            .takes_value(true)
+            .possible_values(["text", "json", "jsonl"]),
+    ]
+}
+
+fn templates_arg() -> Arg<'static> {
+    Arg::new("templates")
+        .help("http checks written as toml or yaml, files or directories of them, run as modules")
+        .long("templates")
+        .short('t')
+        .takes_value(true)
+        .multiple_occurrences(true)
+}
+
+// every layer merged, and where the config file was
+fn settings(matches: &ArgMatches, cli: Settings) -> Result<(Option<String>, Settings)> {
+    let var = |key: &str| env::var(key).ok();
+    let (path, config) = match config::find_config(matches.value_of("config"), var)? {
+        Some((path, config)) => (Some(path), config),
+        None => (None, ConfigFile::default()),
+    };
+    let profile = matches
+        .value_of("profile")
+        .map(|profile| profile.to_string())
+        .or_else(|| var("TRICODER_PROFILE"));
+    let settings = config::merge(cli, Settings::from_env(var)?, config, profile)?;
+    Ok((path, settings))
+}
+
+// only what is on the command line
+fn cli_settings(matches: &ArgMatches) -> Result<Settings> {
+    let string = |name: &str| matches.value_of(name).map(|value| value.to_string());
+    let strings = |name: &str| {
+        matches
+            .values_of(name)
+            .map(|values| values.map(|value| value.to_string()).collect())
+    };
+    let flag = |name: &str| matches.is_present(name).then_some(true);
+    let ip_version = if matches.is_present("ipv4") {
+        Some(String::from("ipv4"))
+    } else if matches.is_present("ipv6") {
+        Some(String::from("ipv6"))
+    } else {
+        None
+    };
+    Ok(Settings {
+        ports: string("ports"),
+        ip_version,
+        service_detection: flag("service-detection"),
+        depth: value(matches, "depth")?,
+        wordlist: string("wordlist"),
+        resolvers: strings("resolver"),
+        resolver_rate: value(matches, "resolver-rate")?,
+        takeover_fingerprints: string("takeover-fingerprints"),
+        proxy: string("proxy"),
+        user_agent: string("user-agent"),
+        headers: strings("header"),
+        cookie: string("cookie"),
+        basic_auth: string("basic-auth"),
+        bearer: string("bearer"),
+        verify_tls: flag("verify-tls"),
+        subdomains_timeout: value(matches, "subdomains-timeout")?,
+        http_timeout: value(matches, "http-timeout")?,
+        probe_timeout: value(matches, "probe-timeout")?,
+        subdomains_concurrency: value(matches, "subdomains-concurrency")?,
+        dns_concurrency: value(matches, "dns-concurrency")?,
+        ports_concurrency: value(matches, "ports-concurrency")?,
+        http_concurrency: value(matches, "http-concurrency")?,
+        rate: value(matches, "rate")?,
+        host_rate: value(matches, "host-rate")?,
+        jitter: value(matches, "jitter")?,
+        modules: strings("modules"),
+        exclude_modules: strings("exclude-modules"),
+        tags: strings("tags"),
+        intrusive: flag("intrusive"),
+        templates: strings("templates"),
+        git_dump: string("git-dump"),
+        output: string("output"),
+    })
+}
+
+fn value<T>(matches: &ArgMatches, name: &str) -> Result<Option<T>, clap::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    matches
+        .is_present(name)
+        .then(|| matches.value_of_t(name))
+        .transpose()
+}
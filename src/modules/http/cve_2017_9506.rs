@@ -1,9 +1,9 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{excerpt, Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 
 pub struct Cve2017_9506 {}
 
@@ -13,6 +13,8 @@ impl Cve2017_9506 {
     }
 }
 
+crate::register_http_module!(Cve2017_9506::new());
+
 impl Module for Cve2017_9506 {
     fn name(&self) -> String {
         String::from("http/cve_2017_9506")
@@ -20,15 +22,26 @@ impl Module for Cve2017_9506 {
     fn description(&self) -> String {
         String::from("Check for cve")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["cve"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![
+            String::from("GET {endpoint}/plugins/servlet/oauth/users/icon-uri?consumerUri=https://google.com/robots.txt"),
+        ]
+    }
 }
 
 #[async_trait]
 impl HttpModule for Cve2017_9506 {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!(
             "{}/plugins/servlet/oauth/users/icon-uri?consumerUri=ttps://google.com/robots.txt",
             &endpoint
@@ -40,7 +53,11 @@ impl HttpModule for Cve2017_9506 {
         }
         let body = res.text().await?;
         if body.contains("user-agent: *") && body.contains("disallow") {
-            return Ok(Some(HttpFinding::Cve2017_9506(url)));
+            let finding = Finding::new(self.name(), Severity::Medium, url.clone())
+                .with_evidence(format!("GET {}", url), excerpt(&body, 200))
+                .with_cve("CVE-2017-9506")
+                .with_remediation("Upgrade Atlassian OAuth plugin (Jira/Confluence) to a fixed version, it can be used for SSRF");
+            return Ok(Some(finding));
         }
         Ok(None)
     }
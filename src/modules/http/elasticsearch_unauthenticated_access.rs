@@ -1,9 +1,9 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 pub struct ElasticsearchUnauthenticatedAccess {}
@@ -14,6 +14,8 @@ impl ElasticsearchUnauthenticatedAccess {
     }
 }
 
+crate::register_http_module!(ElasticsearchUnauthenticatedAccess::new());
+
 impl Module for ElasticsearchUnauthenticatedAccess {
     fn name(&self) -> String {
         String::from("http/elasticsearch_unauthenticated_access")
@@ -21,6 +23,15 @@ impl Module for ElasticsearchUnauthenticatedAccess {
     fn description(&self) -> String {
         String::from("Check for elasticsearch Unauthenticated Access")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["unauthenticated"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::High)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}")]
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -33,10 +44,10 @@ struct ElasticsearchInfo {
 impl HttpModule for ElasticsearchUnauthenticatedAccess {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
-        let url = format!("{}", &endpoint);
+    ) -> Result<Option<Finding>, Error> {
+        let url = endpoint.to_string();
         let res = http_client.get(&url).send().await?;
 
         if !res.status().is_success() {
@@ -49,7 +60,16 @@ impl HttpModule for ElasticsearchUnauthenticatedAccess {
         };
 
         if info.tagline.to_lowercase().contains("you know,for search") {
-            return Ok(Some(HttpFinding::ElasticsearchUnauthenticatedAccess(url)));
+            let finding = Finding::new(self.name(), Severity::High, url.clone())
+                .with_evidence(
+                    format!("GET {}", url),
+                    format!(
+                        "name: {}, cluster_name: {}, tagline: {}",
+                        info.name, info.cluster_name, info.tagline
+                    ),
+                )
+                .with_remediation("Enable Elasticsearch security or keep it off public networks");
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -1,10 +1,10 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
 use regex::Regex;
-use reqwest::Client;
 
 pub struct GitConfigDisclosure {
     git_config_regex: Regex,
@@ -26,6 +26,8 @@ impl GitConfigDisclosure {
     }
 }
 
+crate::register_http_module!(GitConfigDisclosure::new());
+
 impl Module for GitConfigDisclosure {
     fn name(&self) -> String {
         String::from("http/git_config_disclosure")
@@ -34,15 +36,24 @@ impl Module for GitConfigDisclosure {
     fn description(&self) -> String {
         String::from("looks for configs")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["git", "disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/.git/config")]
+    }
 }
 
 #[async_trait]
 impl HttpModule for GitConfigDisclosure {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!("{}/.git/config", &endpoint);
         let res = http_client.get(&url).send().await?;
 
@@ -51,8 +62,18 @@ impl HttpModule for GitConfigDisclosure {
         }
 
         let body = res.text().await?;
-        if self.is_git_config_file(body).await? {
-            return Ok(Some(HttpFinding::GitConfigDisclosure(url)));
+        if self.is_git_config_file(body.clone()).await? {
+            let branch = self
+                .git_config_regex
+                .find(&body)
+                .map(|branch| branch.as_str().to_string())
+                .unwrap_or_default();
+            let finding = Finding::new(self.name(), Severity::Medium, url.clone())
+                .with_evidence(format!("GET {}", url), branch)
+                .with_remediation(
+                    "Remove the .git directory from the web root or deny access to it",
+                );
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -79,9 +100,7 @@ mod tests {
         remote = origin
         merge = refs/heads/master"#;
         let body2 = "test test test test tes <tle>Index of kerkour.fr</title> test";
-        assert_eq!(
-            false,
-            module.is_git_config_file(body2.to_string()).await.unwrap()
-        );
+        assert!(module.is_git_config_file(body.to_string()).await.unwrap());
+        assert!(!module.is_git_config_file(body2.to_string()).await.unwrap());
     }
 }
@@ -1,10 +1,10 @@
 pub use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 
 use async_trait::async_trait;
-use reqwest::Client;
 
 pub struct GitDirectoryDisclosure {}
 
@@ -14,13 +14,15 @@ impl GitDirectoryDisclosure {
     }
 
     fn is_git_directory_listing(&self, content: &str) -> bool {
-        return content.contains("HEAD")
+        content.contains("HEAD")
             && content.contains("refs")
             && content.contains("config")
             && content.contains("index")
-            && content.contains("objects");
+            && content.contains("objects")
     }
 }
+crate::register_http_module!(GitDirectoryDisclosure::new());
+
 impl Module for GitDirectoryDisclosure {
     fn name(&self) -> String {
         String::from("http/git_directory_disclosute")
@@ -28,15 +30,24 @@ impl Module for GitDirectoryDisclosure {
     fn description(&self) -> String {
         String::from("Check for .git/ directory disclosure")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["git", "disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/.git/")]
+    }
 }
 
 #[async_trait]
 impl HttpModule for GitDirectoryDisclosure {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!("{}/.git/", &endpoint);
         let res = http_client.get(&url).send().await?;
 
@@ -46,7 +57,15 @@ impl HttpModule for GitDirectoryDisclosure {
 
         let body = res.text().await?;
         if self.is_git_directory_listing(&body) {
-            return Ok(Some(HttpFinding::GitDirectoryDisclosure(url)));
+            let finding = Finding::new(self.name(), Severity::Medium, url.clone())
+                .with_evidence(
+                    format!("GET {}", url),
+                    String::from("listing shows HEAD, refs, config, index and objects"),
+                )
+                .with_remediation(
+                    "Remove the .git directory from the web root or deny access to it",
+                );
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -73,7 +92,7 @@ mod tests {
 
         let body2 = "lol lol lol ol ol< LO> OL<tle>Index of kerkour.fr</title> sdsds";
 
-        assert_eq!(true, module.is_git_directory_listing(body));
-        assert_eq!(false, module.is_git_directory_listing(body2));
+        assert!(module.is_git_directory_listing(body));
+        assert!(!module.is_git_directory_listing(body2));
     }
 }
@@ -1,9 +1,9 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{excerpt, Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 
 pub struct GitHeadDisclosure {}
 
@@ -13,10 +13,12 @@ impl GitHeadDisclosure {
     }
 
     fn is_head_file(&self, content: &str) -> bool {
-        return Some(0) == content.to_lowercase().trim().find("ref:");
+        Some(0) == content.to_lowercase().trim().find("ref:")
     }
 }
 
+crate::register_http_module!(GitHeadDisclosure::new());
+
 impl Module for GitHeadDisclosure {
     fn name(&self) -> String {
         String::from("http/git_head_disclosure")
@@ -24,15 +26,24 @@ impl Module for GitHeadDisclosure {
     fn description(&self) -> String {
         String::from("check for .git/HEAD")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["git", "disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/.git/HEAD")]
+    }
 }
 
 #[async_trait]
 impl HttpModule for GitHeadDisclosure {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
+    ) -> Result<Option<Finding>, Error> {
         let url = format!("{}/.git/HEAD", &endpoint);
         let res = http_client.get(&url).send().await?;
 
@@ -41,7 +52,12 @@ impl HttpModule for GitHeadDisclosure {
         }
         let body = res.text().await?;
         if self.is_head_file(&body) {
-            return Ok(Some(HttpFinding::GitHeadDisclosure(url)));
+            let finding = Finding::new(self.name(), Severity::Medium, url.clone())
+                .with_evidence(format!("GET {}", url), excerpt(&body, 100))
+                .with_remediation(
+                    "Remove the .git directory from the web root or deny access to it",
+                );
+            return Ok(Some(finding));
         }
         Ok(None)
     }
@@ -58,8 +74,8 @@ mod tests {
         let body2 = r#"ref: refs/heads/heroku"#;
         let body3 = "test test test test  <tle>Index of kerkour.com</title> test";
 
-        assert_eq!(true, module.is_head_file(body));
-        assert_eq!(true, module.is_head_file(body2));
-        assert_eq!(false, module.is_head_file(body3));
+        assert!(module.is_head_file(body));
+        assert!(module.is_head_file(body2));
+        assert!(!module.is_head_file(body3));
     }
 }
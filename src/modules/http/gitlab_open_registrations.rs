@@ -1,9 +1,9 @@
 use crate::{
-    modules::{HttpFinding, HttpModule, Module},
+    http_client::HttpClient,
+    modules::{Finding, HttpModule, Module, Severity},
     Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 
 pub struct GitlabOpenRegistration {}
 
@@ -13,6 +13,8 @@ impl GitlabOpenRegistration {
     }
 }
 
+crate::register_http_module!(GitlabOpenRegistration::new());
+
 impl Module for GitlabOpenRegistration {
     fn name(&self) -> String {
         String::from("http/gitlab_open_regsitration")
@@ -20,6 +22,15 @@ impl Module for GitlabOpenRegistration {
     fn description(&self) -> String {
         String::from("check if gitlab instace ca be registered")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["misconfiguration"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}")]
+    }
 }
 
 #[async_trait]
@@ -29,10 +40,10 @@ impl HttpModule for GitlabOpenRegistration {
     //returns the url
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
-        let url = format!("{}", &endpoint);
+    ) -> Result<Option<Finding>, Error> {
+        let url = endpoint.to_string();
         let res = http_client.get(&url).send().await?;
 
         if !res.status().is_success() {
@@ -42,7 +53,13 @@ impl HttpModule for GitlabOpenRegistration {
         let body = res.text().await?;
 
         if body.contains("This is a self-managed instance of GitLab") && body.contains("Register") {
-            return Ok(Some(HttpFinding::GitlabOpenRegistration(url)));
+            let finding = Finding::new(self.name(), Severity::Medium, url.clone())
+                .with_evidence(
+                    format!("GET {}", url),
+                    String::from("This is a self-managed instance of GitLab ... Register"),
+                )
+                .with_remediation("Disable sign-up or restrict it to allowed email domains");
+            return Ok(Some(finding));
         }
         Ok(None)
     }
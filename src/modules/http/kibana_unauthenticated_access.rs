@@ -1,49 +0,0 @@
-use crate::{
-    modules::{HttpFinding, HttpModule, Module},
-    Error,
-};
-use async_trait::async_trait;
-use reqwest::Client;
-
-pub struct KibanaUnauthenticatedAccess {}
-
-impl KibanaUnauthenticatedAccess {
-    pub fn new() -> Self {
-        KibanaUnauthenticatedAccess {}
-    }
-}
-
-impl Module for KibanaUnauthenticatedAccess {
-    fn name(&self) -> String {
-        String::from("http/kibana_unauthenticated_access")
-    }
-
-    fn description(&self) -> String {
-        String::from("looks for exposed kibana ")
-    }
-}
-
-#[async_trait]
-impl HttpModule for KibanaUnauthenticatedAccess {
-    async fn scan(
-        &self,
-        http_client: &Client,
-        endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error> {
-        let url = format!("{}", &endpoint);
-        let res = http_client.get(&url).send().await?;
-
-        if !res.status().is_success() {
-            return Ok(None);
-        }
-
-        let body = res.text().await?;
-        if body.contains(r#"</head><body kbn-chrome id="kibana-body"><kbn-initial-state"#)
-        || body.contains(r#"<div class="ui-app-loading"><h1><strong>Kibana</strong><small>&nbsp;isloading."#)
-        || Some(0) == body.find(r#"|| body.contains("#)
-        || body.contains(r#"<div class="kibanaWelcomeLogo"></div></div></div><div class="kibanaWelcomeText">Loading Kibana</div></div>"#) {
-            return Ok(Some(HttpFinding::KibanaUnauthenticatedAccess(url)));
-        }
-        Ok(None)
-    }
-}
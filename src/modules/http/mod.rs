@@ -1,28 +1,15 @@
 mod directory_listing_disclosure;
-pub use directory_listing_disclosure::DirectoryListingDisclosure;
-mod dotenv_disclosure;
-pub use dotenv_disclosure::DotEnvDisclosure;
 mod ds_store_disclosure;
-pub use ds_store_disclosure::DsStoreDisclosure;
 // mod traefik_dashboard_unauthenticated_access;
 // pub use traefik_dashboard_unauthenticated_access::TraefikDashboardUnauthenticatedAccess;
-mod prometheus_dashboard_unauthenticated_access;
-pub use prometheus_dashboard_unauthenticated_access::PrometheusDashboardUnauthenticatedAccess;
-mod kibana_unauthenticated_access;
-pub use kibana_unauthenticated_access::KibanaUnauthenticatedAccess;
-mod gitlab_open_registrations;
-pub use gitlab_open_registrations::GitlabOpenRegistration;
-mod git_head_disclosure;
-pub use git_head_disclosure::GitHeadDisclosure;
-mod git_directory_disclosure;
-pub use git_directory_disclosure::GitDirectoryDisclosure;
-mod git_config_disclosure;
-pub use git_config_disclosure::GitConfigDisclosure;
-mod etcd_unauthenticated_access;
-pub use etcd_unauthenticated_access::EtcdUnauthenticatedAccess;
 mod cve_2017_9506;
-pub use cve_2017_9506::Cve2017_9506;
 mod cve_2018_7600;
-pub use cve_2018_7600::Cve2018_7600;
 mod elasticsearch_unauthenticated_access;
-pub use elasticsearch_unauthenticated_access::ElasticsearchUnauthenticatedAccess;
+mod git_config_disclosure;
+mod git_directory_disclosure;
+mod git_dump;
+mod git_head_disclosure;
+mod gitlab_open_registrations;
+mod template;
+pub use git_dump::GitDump;
+pub use template::{builtin_templates, read_templates};
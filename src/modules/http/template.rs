@@ -0,0 +1,551 @@
+use crate::{
+    http_client::HttpClient,
+    modules::{excerpt, Finding, HttpModule, Module, Severity},
+    Error,
+};
+use async_trait::async_trait;
+use regex::Regex;
+use reqwest::{header::HeaderName, Method};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+// the templates shipped with the binary
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "dotenv.toml",
+        include_str!("../../../data/templates/dotenv.toml"),
+    ),
+    (
+        "etcd_unauthenticated_access.toml",
+        include_str!("../../../data/templates/etcd_unauthenticated_access.toml"),
+    ),
+    (
+        "kibana_unauthenticated_access.toml",
+        include_str!("../../../data/templates/kibana_unauthenticated_access.toml"),
+    ),
+    (
+        "prometheus_dashboard_unauthenticated_access.toml",
+        include_str!("../../../data/templates/prometheus_dashboard_unauthenticated_access.toml"),
+    ),
+];
+
+// an http check written as data, one request and what the answer must look like
+// see data/templates for examples, yaml files take the same keys
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Template {
+    pub name: String,
+    pub description: String,
+    pub severity: Severity,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub cve: Option<String>,
+    #[serde(default)]
+    pub remediation: Option<String>,
+    pub request: TemplateRequest,
+    // how the matchers are combined
+    #[serde(default)]
+    pub matchers_condition: Condition,
+    pub matchers: Vec<Matcher>,
+    // values copied from the response into the evidence
+    #[serde(default)]
+    pub extractors: Vec<Extractor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    // appended to the endpoint: "/version", "" is the endpoint itself
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+fn default_method() -> String {
+    String::from("GET")
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Condition {
+    #[default]
+    And,
+    Or,
+}
+
+impl Condition {
+    fn check(&self, mut results: impl Iterator<Item = bool>) -> bool {
+        match self {
+            Condition::And => results.all(|result| result),
+            Condition::Or => results.any(|result| result),
+        }
+    }
+}
+
+// which part of the response a matcher or an extractor looks at
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Part {
+    #[default]
+    Body,
+    // "name: value" lines
+    Header,
+    // the headers then the body
+    All,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatcherType {
+    Status,
+    Word,
+    Regex,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Matcher {
+    #[serde(rename = "type")]
+    pub matcher_type: MatcherType,
+    #[serde(default)]
+    pub part: Part,
+    #[serde(default)]
+    pub status: Vec<u16>,
+    #[serde(default)]
+    pub words: Vec<String>,
+    #[serde(default)]
+    pub regex: Vec<String>,
+    // how the words or the regexes are combined, a status matches any of the list
+    #[serde(default)]
+    pub condition: Condition,
+    // matches when it does not
+    #[serde(default)]
+    pub negative: bool,
+    #[serde(skip)]
+    compiled: Vec<Regex>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extractor {
+    pub name: String,
+    #[serde(default)]
+    pub part: Part,
+    pub regex: Vec<String>,
+    // the capture group kept, by default the first one or the whole match
+    #[serde(default)]
+    pub group: Option<usize>,
+    #[serde(skip)]
+    compiled: Vec<Regex>,
+}
+
+// the response as the matchers see it
+struct Response {
+    status: u16,
+    headers: String,
+    body: String,
+}
+
+impl Response {
+    fn part(&self, part: Part) -> String {
+        match part {
+            Part::Body => self.body.clone(),
+            Part::Header => self.headers.clone(),
+            Part::All => format!("{}\n{}", self.headers, self.body),
+        }
+    }
+}
+
+// more than this is noise in a report
+const MAX_EXTRACTED: usize = 10;
+
+impl Template {
+    pub fn parse(content: &str, format: &str) -> Result<Self, Error> {
+        let template: Template = match format {
+            "toml" => toml::from_str(content).map_err(|err| Error::Template(err.to_string()))?,
+            "yaml" | "yml" => {
+                serde_yaml::from_str(content).map_err(|err| Error::Template(err.to_string()))?
+            }
+            _ => {
+                return Err(Error::Template(format!(
+                    "unknown template format: {}",
+                    format
+                )))
+            }
+        };
+        template.compile()
+    }
+
+    // checks what serde can not and compiles the regexes once
+    fn compile(mut self) -> Result<Self, Error> {
+        let invalid = |reason: String| Error::Template(format!("{}: {}", self.name, reason));
+        Method::from_bytes(self.request.method.as_bytes())
+            .map_err(|_| invalid(format!("invalid method {}", self.request.method)))?;
+        for name in self.request.headers.keys() {
+            HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| invalid(format!("invalid header {}", name)))?;
+        }
+        if self.matchers.is_empty() {
+            return Err(invalid(String::from("no matchers")));
+        }
+        for matcher in &mut self.matchers {
+            let empty = match matcher.matcher_type {
+                MatcherType::Status => matcher.status.is_empty(),
+                MatcherType::Word => matcher.words.is_empty(),
+                MatcherType::Regex => matcher.regex.is_empty(),
+            };
+            if empty {
+                return Err(invalid(format!(
+                    "{:?} matcher without values",
+                    matcher.matcher_type
+                )));
+            }
+            matcher.compiled = compile_regexes(&matcher.regex).map_err(invalid)?;
+        }
+        for extractor in &mut self.extractors {
+            extractor.compiled = compile_regexes(&extractor.regex).map_err(invalid)?;
+        }
+        Ok(self)
+    }
+
+    fn matches(&self, response: &Response) -> bool {
+        self.matchers_condition.check(
+            self.matchers
+                .iter()
+                .map(|matcher| matcher.matches(response)),
+        )
+    }
+
+    // one line per extractor that found something
+    fn extract(&self, response: &Response) -> Vec<String> {
+        self.extractors
+            .iter()
+            .filter_map(|extractor| {
+                let values = extractor.extract(response);
+                (!values.is_empty()).then(|| format!("{}: {}", extractor.name, values.join(", ")))
+            })
+            .collect()
+    }
+}
+
+impl Matcher {
+    fn matches(&self, response: &Response) -> bool {
+        let matched = match self.matcher_type {
+            MatcherType::Status => self.status.contains(&response.status),
+            MatcherType::Word => {
+                let text = response.part(self.part);
+                self.condition
+                    .check(self.words.iter().map(|word| text.contains(word.as_str())))
+            }
+            MatcherType::Regex => {
+                let text = response.part(self.part);
+                self.condition
+                    .check(self.compiled.iter().map(|regex| regex.is_match(&text)))
+            }
+        };
+        matched != self.negative
+    }
+}
+
+impl Extractor {
+    fn extract(&self, response: &Response) -> Vec<String> {
+        let text = response.part(self.part);
+        let mut values: Vec<String> = Vec::new();
+        for regex in &self.compiled {
+            let group = self
+                .group
+                .unwrap_or(if regex.captures_len() > 1 { 1 } else { 0 });
+            for captures in regex.captures_iter(&text) {
+                if let Some(value) = captures.get(group) {
+                    let value = value.as_str().trim().to_string();
+                    if !values.contains(&value) {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+        values.truncate(MAX_EXTRACTED);
+        values
+    }
+}
+
+fn compile_regexes(regexes: &[String]) -> Result<Vec<Regex>, String> {
+    regexes
+        .iter()
+        .map(|regex| Regex::new(regex).map_err(|err| err.to_string()))
+        .collect()
+}
+
+impl Module for Template {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+    fn tags(&self) -> Vec<&str> {
+        self.tags.iter().map(String::as_str).collect()
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(self.severity)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![format!(
+            "{} {{endpoint}}{}",
+            self.request.method, self.request.path
+        )]
+    }
+}
+
+#[async_trait]
+impl HttpModule for Template {
+    async fn scan(
+        &self,
+        http_client: &HttpClient,
+        endpoint: &str,
+    ) -> Result<Option<Finding>, Error> {
+        let url = format!("{}{}", endpoint, self.request.path);
+        // checked in compile
+        let method = Method::from_bytes(self.request.method.as_bytes())
+            .map_err(|err| Error::Template(err.to_string()))?;
+        let mut request = http_client.request(method, &url);
+        for (name, value) in &self.request.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|err| Error::Template(err.to_string()))?;
+            request = request.header(name, value);
+        }
+        if let Some(body) = &self.request.body {
+            request = request.body(body.clone());
+        }
+        let res = request.send().await?;
+
+        let status = res.status().as_u16();
+        let headers: Vec<String> = res
+            .headers()
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+            .collect();
+        let response = Response {
+            status,
+            headers: headers.join("\n"),
+            body: res.text().await?,
+        };
+        if !self.matches(&response) {
+            return Ok(None);
+        }
+
+        let extracted = self.extract(&response);
+        let evidence = if extracted.is_empty() {
+            excerpt(&response.body, 200)
+        } else {
+            extracted.join("\n")
+        };
+        let mut finding = Finding::new(self.name(), self.severity, url.clone())
+            .with_evidence(format!("{} {}", self.request.method, url), evidence);
+        if let Some(cve) = &self.cve {
+            finding = finding.with_cve(cve);
+        }
+        if let Some(remediation) = &self.remediation {
+            finding = finding.with_remediation(remediation);
+        }
+        Ok(Some(finding))
+    }
+}
+
+pub fn builtin_templates() -> Vec<Template> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|(file, content)| {
+            Template::parse(content, "toml")
+                .unwrap_or_else(|err| panic!("templates: parsing {}: {}", file, err))
+        })
+        .collect()
+}
+
+// a template file, or every .toml, .yaml and .yml file of a directory
+pub fn read_templates(path: &str) -> Result<Vec<Template>, Error> {
+    let read_error = |err: std::io::Error| Error::Template(format!("{}: {}", path, err));
+    let path = Path::new(path);
+    let mut files = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path).map_err(read_error)? {
+            files.push(entry.map_err(read_error)?.path());
+        }
+        files.sort();
+    } else {
+        files.push(path.to_path_buf());
+    }
+
+    let mut templates = Vec::new();
+    for file in files {
+        let format = file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        if path.is_dir() && !["toml", "yaml", "yml"].contains(&format.as_str()) {
+            continue;
+        }
+        let content = fs::read_to_string(&file)
+            .map_err(|err| Error::Template(format!("{}: {}", file.display(), err)))?;
+        let template = Template::parse(&content, &format)
+            .map_err(|err| Error::Template(format!("{}: {}", file.display(), err)))?;
+        templates.push(template);
+    }
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builtin_templates, read_templates, Template};
+    use crate::{
+        http_client::HttpClient,
+        modules::{self, HttpModule, Severity},
+        testing::http_server,
+    };
+    use reqwest::Client;
+    use std::fs;
+
+    const TEMPLATE: &str = r#"
+name: http/grafana_login
+description: finds grafana and its version
+severity: info
+tags: [exposure]
+request:
+  path: /login
+  headers:
+    X-Check: tricoder
+matchers-condition: and
+matchers:
+  - type: status
+    status: [200]
+  - type: word
+    words: ["grafana-app", "Grafana"]
+    condition: or
+  - type: regex
+    part: header
+    regex: ["(?i)content-type: text/html"]
+  - type: word
+    words: ["Unauthorized"]
+    negative: true
+extractors:
+  - name: version
+    regex: ['"version":"([0-9.]+)"']
+"#;
+
+    #[test]
+    fn builtin_templates_parse() {
+        let templates = builtin_templates();
+        assert!(templates
+            .iter()
+            .any(|template| template.name == "http/dotenv"));
+    }
+
+    #[test]
+    fn templates_directory() {
+        let dir = std::env::temp_dir().join(format!("tricoder-templates-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("grafana.yaml"), TEMPLATE).unwrap();
+        fs::write(dir.join("notes.txt"), "not a template").unwrap();
+        let dir_path = dir.to_str().unwrap().to_string();
+
+        let templates = read_templates(&dir_path).unwrap();
+        assert_eq!(1, templates.len());
+        assert_eq!("http/grafana_login", templates[0].name);
+        let http_modules = modules::http_modules(std::slice::from_ref(&dir_path)).unwrap();
+        assert_eq!(modules::all_http_modules().len() + 1, http_modules.len());
+
+        // a template can not shadow a module
+        let taken = TEMPLATE.replace("http/grafana_login", "http/dotenv");
+        fs::write(dir.join("grafana.yaml"), taken).unwrap();
+        assert!(modules::http_modules(&[dir_path]).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dotenv_keys_only() {
+        let dotenv = builtin_templates()
+            .into_iter()
+            .find(|template| template.name == "http/dotenv")
+            .unwrap();
+        let addr = http_server(vec![
+            (
+                "/.env",
+                200,
+                Vec::new(),
+                b"APP_KEY=base64:secret\nDB_PASSWORD = hunter2\n".to_vec(),
+            ),
+            ("/spa/.env", 200, Vec::new(), b"<html></html>".to_vec()),
+        ])
+        .await;
+        let http_client = HttpClient::from(Client::new());
+
+        let finding = dotenv
+            .scan(&http_client, &format!("http://{}", addr))
+            .await
+            .unwrap()
+            .unwrap();
+        let evidence = finding.evidence.unwrap();
+        assert_eq!("keys: APP_KEY, DB_PASSWORD", evidence.response);
+        assert!(!evidence.response.contains("hunter2"));
+        // a single page app answering 200 to everything
+        let finding = dotenv
+            .scan(&http_client, &format!("http://{}/spa", addr))
+            .await
+            .unwrap();
+        assert!(finding.is_none());
+    }
+
+    #[test]
+    fn invalid_templates() {
+        let no_matchers = "name = \"a\"\ndescription = \"a\"\nseverity = \"low\"\nmatchers = []\n[request]\npath = \"/\"";
+        assert!(Template::parse(no_matchers, "toml").is_err());
+        let bad_regex = TEMPLATE.replace("([0-9.]+)", "([0-9.]+");
+        assert!(Template::parse(&bad_regex, "yaml").is_err());
+        let unknown_key = TEMPLATE.replace("negative: true", "negativ: true");
+        assert!(Template::parse(&unknown_key, "yaml").is_err());
+        assert!(Template::parse(TEMPLATE, "json").is_err());
+    }
+
+    #[tokio::test]
+    async fn yaml_template() {
+        let template = Template::parse(TEMPLATE, "yaml").unwrap();
+        let html = vec![String::from("Content-Type: text/html")];
+        let addr = http_server(vec![
+            (
+                "/login",
+                200,
+                html.clone(),
+                br#"<div class="grafana-app"></div>{"version":"9.1.0"}"#.to_vec(),
+            ),
+            (
+                "/other/login",
+                200,
+                html,
+                b"<h1>Unauthorized</h1> grafana-app".to_vec(),
+            ),
+        ])
+        .await;
+        let http_client = HttpClient::from(Client::new());
+
+        let finding = template
+            .scan(&http_client, &format!("http://{}", addr))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(Severity::Info, finding.severity);
+        assert_eq!("version: 9.1.0", finding.evidence.unwrap().response);
+
+        let finding = template
+            .scan(&http_client, &format!("http://{}/other", addr))
+            .await
+            .unwrap();
+        assert!(finding.is_none());
+    }
+}
@@ -1,81 +1,331 @@
-use crate::Error;
+use crate::{
+    dns::{DnsRecord, Resolver},
+    http_client::HttpClient,
+    services::{Service, TlsCertificate},
+    Error,
+};
 use async_trait::async_trait;
-use reqwest::Client;
+use chrono::{DateTime, Utc};
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use std::{fmt, net::IpAddr, sync::Arc};
 
 mod http;
+mod registry;
 mod subdomains;
+pub use http::GitDump;
+pub use registry::{HttpRegistration, SubdomainsRegistration};
+pub use subdomains::{read_wordlist, Bruteforce};
 
-pub fn all_http_modules() -> Vec<Box<dyn HttpModule>> {
-    return vec![
-        Box::new(http::DsStoreDisclosure::new()),
-        Box::new(http::DotEnvDisclosure::new()),
-        Box::new(http::DirectoryListingDisclosure::new()),
-        // Box::new(http::TraefikDashboardUnauthenticatedAccess::new()),
-        Box::new(http::PrometheusDashboardUnauthenticatedAccess::new()),
-        Box::new(http::KibanaUnauthenticatedAccess::new()),
-        Box::new(http::GitlabOpenRegistration::new()),
-        Box::new(http::GitHeadDisclosure::new()),
-        Box::new(http::GitDirectoryDisclosure::new()),
-        Box::new(http::GitConfigDisclosure::new()),
-        Box::new(http::EtcdUnauthenticatedAccess::new()),
-        Box::new(http::Cve2017_9506::new()),
-        Box::new(http::Cve2018_7600::new()),
-        Box::new(http::ElasticsearchUnauthenticatedAccess::new()),
-    ];
-}
-
-pub fn all_subdomains_modules() -> Vec<Box<dyn SubdomainModule>> {
-    return vec![
-        Box::new(subdomains::Crtsh::new()),
-        Box::new(subdomains::Dnsrepo::new()),
-        Box::new(subdomains::WebArchive::new()),
-    ];
-}
-
-pub trait Module {
+// the registered modules and the builtin templates
+pub fn all_http_modules() -> Vec<Arc<dyn HttpModule>> {
+    let mut modules = registry::registered_http_modules();
+    for template in http::builtin_templates() {
+        modules.push(Arc::new(template));
+    }
+    modules
+}
+
+// all_http_modules plus the templates of these files or directories
+// a template can not take the name of another module
+pub fn http_modules(templates: &[String]) -> Result<Vec<Arc<dyn HttpModule>>, Error> {
+    let mut modules = all_http_modules();
+    for path in templates {
+        for template in http::read_templates(path)? {
+            if modules.iter().any(|module| module.name() == template.name) {
+                return Err(Error::Template(format!(
+                    "{}: {} is already a module",
+                    path, template.name
+                )));
+            }
+            modules.push(Arc::new(template));
+        }
+    }
+    Ok(modules)
+}
+
+// bruteforce is not registered, it only runs with a wordlist
+pub fn all_subdomains_modules(dns_resolver: &Resolver) -> Vec<Box<dyn SubdomainModule>> {
+    registry::registered_subdomains_modules(dns_resolver)
+}
+
+// modules with this tag send payloads or change state on the target
+pub const INTRUSIVE: &str = "intrusive";
+
+// which modules run
+// globs on the name (http/git_*), tags, and the intrusive ones only when asked
+#[derive(Debug, Clone, Default)]
+pub struct ModuleFilter {
+    // empty is every module
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    // empty is any tag
+    tags: Vec<String>,
+    intrusive: bool,
+}
+
+impl ModuleFilter {
+    pub fn new(
+        include: &[String],
+        exclude: &[String],
+        tags: Vec<String>,
+        intrusive: bool,
+    ) -> Result<Self, Error> {
+        let patterns = |globs: &[String]| -> Result<Vec<Pattern>, Error> {
+            globs
+                .iter()
+                .map(|glob| {
+                    Pattern::new(glob)
+                        .map_err(|err| Error::InvalidModulePattern(format!("{}: {}", glob, err)))
+                })
+                .collect()
+        };
+        Ok(ModuleFilter {
+            include: patterns(include)?,
+            exclude: patterns(exclude)?,
+            tags,
+            intrusive,
+        })
+    }
+
+    pub fn allows<M: Module + ?Sized>(&self, module: &M) -> bool {
+        let name = module.name();
+        let tags = module.tags();
+        (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(&name)))
+            && !self.exclude.iter().any(|glob| glob.matches(&name))
+            && (self.tags.is_empty() || self.tags.iter().any(|tag| tags.contains(&tag.as_str())))
+            && (self.intrusive || !tags.contains(&INTRUSIVE))
+    }
+}
+
+pub trait Module: Send + Sync {
     fn name(&self) -> String;
     fn description(&self) -> String;
+    // what kind of check it is: git, cve, disclosure, intrusive...
+    fn tags(&self) -> Vec<&str> {
+        Vec::new()
+    }
+    // of the worst finding it reports, None when it only finds names
+    fn severity(&self) -> Option<Severity> {
+        None
+    }
+    // what it sends, {endpoint} and {domain} are filled in at scan time
+    fn requests(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+// what tricoder modules prints about a module
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    // subdomains, http or dns
+    pub kind: &'static str,
+    pub description: String,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    pub intrusive: bool,
+    pub requests: Vec<String>,
+}
+
+impl ModuleInfo {
+    pub fn new<M: Module + ?Sized>(kind: &'static str, module: &M) -> Self {
+        ModuleInfo {
+            name: module.name(),
+            kind,
+            description: module.description(),
+            tags: module.tags().into_iter().map(str::to_string).collect(),
+            severity: module.severity(),
+            intrusive: module.tags().contains(&INTRUSIVE),
+            requests: module.requests(),
+        }
+    }
 }
 
 #[async_trait]
 pub trait SubdomainModule: Module {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>, Error>;
+    async fn enumerate(&self, http_client: &HttpClient, domain: &str)
+        -> Result<Enumeration, Error>;
 }
-#[derive(Debug, Clone)]
+
+// what a subdomain module found for a domain
+// most only find names, findings are for things like an open zone transfer
+#[derive(Debug, Clone, Default)]
+pub struct Enumeration {
+    pub subdomains: Vec<String>,
+    pub findings: Vec<Finding>,
+}
+
+impl From<Vec<String>> for Enumeration {
+    fn from(subdomains: Vec<String>) -> Self {
+        Enumeration {
+            subdomains,
+            findings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Subdomain {
     pub domain: String,
+    // filled by dns::resolves
+    pub addresses: Vec<IpAddr>,
+    pub cnames: Vec<String>,
+    pub records: Vec<DnsRecord>,
     pub open_ports: Vec<Port>,
+    // findings about the domain itself and not one of its ports
+    pub findings: Vec<Finding>,
 }
-#[derive(Debug, Clone)]
+
+impl Subdomain {
+    pub fn new(domain: String) -> Self {
+        Subdomain {
+            domain,
+            addresses: Vec::new(),
+            cnames: Vec::new(),
+            records: Vec::new(),
+            open_ports: Vec::new(),
+            findings: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Port {
     pub port: u16,
+    // the address the port was found open on
+    pub ip: IpAddr,
+    // only there when service detection ran
+    pub service: Option<Service>,
+    pub certificate: Option<TlsCertificate>,
+    #[serde(skip)]
     pub is_open: bool,
-    pub findings: Vec<HttpFinding>,
+    pub findings: Vec<Finding>,
 }
 
 #[async_trait]
 pub trait HttpModule: Module {
     async fn scan(
         &self,
-        http_client: &Client,
+        http_client: &HttpClient,
         endpoint: &str,
-    ) -> Result<Option<HttpFinding>, Error>;
-}
-#[derive(Debug, Clone)]
-
-pub enum HttpFinding {
-    DsStoreFileDisclosure(String),
-    DotEnvFileDisclosure(String),
-    DirectoryListingDisclosure(String),
-    // TraefikDashboardUnauthenticatedAccess(String),
-    PrometheusDashboardUnauthenticatedAccess(String),
-    KibanaUnauthenticatedAccess(String),
-    GitlabOpenRegistration(String),
-    GitHeadDisclosure(String),
-    GitDirectoryDisclosure(String),
-    GitConfigDisclosure(String),
-    EtcdUnauthenticatedAccess(String),
-    Cve2017_9506(String),
-    Cve2018_7600(String),
-    ElasticsearchUnauthenticatedAccess(String),
+    ) -> Result<Option<Finding>, Error>;
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", severity)
+    }
+}
+
+// what we sent and the part of the answer that made the module fire
+#[derive(Debug, Clone, Serialize)]
+pub struct Evidence {
+    pub request: String,
+    pub response: String,
+}
+
+// what a module found, why and when
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub module: String,
+    pub severity: Severity,
+    pub url: String,
+    pub evidence: Option<Evidence>,
+    pub cves: Vec<String>,
+    pub remediation: Option<String>,
+    pub found_at: DateTime<Utc>,
+}
+
+impl Finding {
+    pub fn new(module: String, severity: Severity, url: String) -> Self {
+        Finding {
+            module,
+            severity,
+            url,
+            evidence: None,
+            cves: Vec::new(),
+            remediation: None,
+            found_at: Utc::now(),
+        }
+    }
+
+    pub fn with_evidence(mut self, request: String, response: String) -> Self {
+        self.evidence = Some(Evidence { request, response });
+        self
+    }
+
+    pub fn with_cve(mut self, cve: &str) -> Self {
+        self.cves.push(cve.to_string());
+        self
+    }
+
+    pub fn with_remediation(mut self, remediation: &str) -> Self {
+        self.remediation = Some(remediation.to_string());
+        self
+    }
+}
+
+// keeps evidence readable when the body is big
+pub fn excerpt(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{all_http_modules, ModuleFilter};
+
+    fn allowed(module_filter: &ModuleFilter) -> Vec<String> {
+        all_http_modules()
+            .iter()
+            .filter(|module| module_filter.allows(module.as_ref()))
+            .map(|module| module.name())
+            .collect()
+    }
+
+    #[test]
+    fn intrusive_modules_opt_in() {
+        let cves =
+            |intrusive| ModuleFilter::new(&[], &[], vec![String::from("cve")], intrusive).unwrap();
+        assert_eq!(vec!["http/cve_2017_9506"], allowed(&cves(false)));
+        assert_eq!(
+            vec!["http/cve_2017_9506", "http/cve_2018_7600"],
+            allowed(&cves(true))
+        );
+        // naming it is not enough
+        let named = ModuleFilter::new(
+            &[String::from("http/cve_2018_7600")],
+            &[],
+            Vec::new(),
+            false,
+        )
+        .unwrap();
+        assert!(allowed(&named).is_empty());
+    }
+
+    #[test]
+    fn invalid_glob() {
+        assert!(ModuleFilter::new(&[String::from("http/[git")], &[], Vec::new(), false).is_err());
+    }
 }
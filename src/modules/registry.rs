@@ -0,0 +1,69 @@
+use crate::{
+    dns::Resolver,
+    modules::{HttpModule, SubdomainModule},
+};
+use std::sync::Arc;
+
+// every module registers itself next to its code with one of the macros below,
+// crates linking tricoder can add theirs the same way
+// the metadata (name, tags, severity, requests) comes from the Module trait
+
+pub struct HttpRegistration {
+    pub new: fn() -> Arc<dyn HttpModule>,
+}
+
+// subdomain modules get the resolver of the scan, most just ignore it
+pub struct SubdomainsRegistration {
+    pub new: fn(&Resolver) -> Box<dyn SubdomainModule>,
+}
+
+inventory::collect!(HttpRegistration);
+inventory::collect!(SubdomainsRegistration);
+
+// register_http_module!(GitHeadDisclosure::new());
+#[macro_export]
+macro_rules! register_http_module {
+    ($new:expr) => {
+        $crate::inventory::submit! {
+            $crate::modules::HttpRegistration {
+                new: || -> ::std::sync::Arc<dyn $crate::modules::HttpModule> {
+                    ::std::sync::Arc::new($new)
+                },
+            }
+        }
+    };
+}
+
+// register_subdomains_module!(|dns_resolver| DnsRecords::new(dns_resolver.clone()));
+#[macro_export]
+macro_rules! register_subdomains_module {
+    (|$dns_resolver:pat_param| $new:expr) => {
+        $crate::inventory::submit! {
+            $crate::modules::SubdomainsRegistration {
+                new: |$dns_resolver: &$crate::dns::Resolver|
+                    -> ::std::boxed::Box<dyn $crate::modules::SubdomainModule> {
+                    ::std::boxed::Box::new($new)
+                },
+            }
+        }
+    };
+}
+
+// sorted by name so the listing and the scans are in the same order every time
+pub fn registered_http_modules() -> Vec<Arc<dyn HttpModule>> {
+    let mut modules: Vec<Arc<dyn HttpModule>> = inventory::iter::<HttpRegistration>
+        .into_iter()
+        .map(|registration| (registration.new)())
+        .collect();
+    modules.sort_by_key(|module| module.name());
+    modules
+}
+
+pub fn registered_subdomains_modules(dns_resolver: &Resolver) -> Vec<Box<dyn SubdomainModule>> {
+    let mut modules: Vec<Box<dyn SubdomainModule>> = inventory::iter::<SubdomainsRegistration>
+        .into_iter()
+        .map(|registration| (registration.new)(dns_resolver))
+        .collect();
+    modules.sort_by_key(|module| module.name());
+    modules
+}
@@ -0,0 +1,190 @@
+use crate::{
+    dns::{self, Resolver},
+    http_client::HttpClient,
+    modules::{Enumeration, Module, Subdomain, SubdomainModule},
+    targets, Error,
+};
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+use std::{
+    collections::{hash_map::RandomState, HashSet},
+    fs,
+    hash::{BuildHasher, Hasher},
+    net::IpAddr,
+};
+
+// how many random labels are resolved to find wildcard records
+const WILDCARD_PROBES: usize = 3;
+
+// Bruteforce resolves word.domain for every word of a wordlist
+// unlike the other modules this one talks to the target's dns servers
+pub struct Bruteforce {
+    dns_resolver: Resolver,
+    words: Vec<String>,
+    concurrency: usize,
+}
+
+impl Bruteforce {
+    pub fn new(dns_resolver: Resolver, words: Vec<String>, concurrency: usize) -> Self {
+        Bruteforce {
+            dns_resolver,
+            words,
+            concurrency,
+        }
+    }
+
+    // addresses a made up name resolves to
+    // if any of them answer the domain has a wildcard record
+    async fn wildcard_addresses(&self, domain: &str) -> HashSet<IpAddr> {
+        let probes = (0..WILDCARD_PROBES).map(|_| format!("{}.{}", random_label(), domain));
+        let answers: Vec<Option<Subdomain>> = stream::iter(probes)
+            .map(|probe| dns::resolves(&self.dns_resolver, Subdomain::new(probe)))
+            .buffer_unordered(WILDCARD_PROBES)
+            .collect()
+            .await;
+        answers
+            .into_iter()
+            .flatten()
+            .flat_map(|subdomain| subdomain.addresses)
+            .collect()
+    }
+}
+
+impl Module for Bruteforce {
+    fn name(&self) -> String {
+        String::from("subdomains/bruteforce")
+    }
+    fn description(&self) -> String {
+        String::from("resolve the words of a wordlist as subdomains, skips wildcard answers")
+    }
+    fn tags(&self) -> Vec<&str> {
+        vec!["dns"]
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![
+            String::from("DNS A/AAAA of 3 random names to detect wildcards"),
+            String::from("DNS A/AAAA <word>.{domain} for every word of the wordlist"),
+        ]
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for Bruteforce {
+    async fn enumerate(
+        &self,
+        _http_client: &HttpClient,
+        domain: &str,
+    ) -> Result<Enumeration, Error> {
+        let wildcard = self.wildcard_addresses(domain).await;
+        if !wildcard.is_empty() {
+            log::info!("{}: {} has a wildcard record", self.name(), domain);
+        }
+        let candidates: Vec<Subdomain> = self
+            .words
+            .iter()
+            .map(|word| Subdomain::new(format!("{}.{}", word, domain)))
+            .collect();
+
+        let resolved: Vec<Option<Subdomain>> = stream::iter(candidates)
+            .map(|subdomain| dns::resolves(&self.dns_resolver, subdomain))
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        // an answer we would also get for a random label says nothing
+        Ok(resolved
+            .into_iter()
+            .flatten()
+            .filter(|subdomain| {
+                subdomain.addresses.is_empty()
+                    || !subdomain
+                        .addresses
+                        .iter()
+                        .all(|address| wildcard.contains(address))
+            })
+            .map(|subdomain| subdomain.domain)
+            .collect::<Vec<String>>()
+            .into())
+    }
+}
+
+// one word per line, same format as the targets file
+pub fn read_wordlist(path: &str) -> Result<Vec<String>, Error> {
+    let content =
+        fs::read_to_string(path).map_err(|err| Error::Wordlist(format!("{}: {}", path, err)))?;
+    Ok(targets::lines(&content)
+        .into_iter()
+        .map(|word| word.trim_matches('.').to_lowercase())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect())
+}
+
+// no rand crate so the std hasher seed does the job
+fn random_label() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    format!("tricoder-{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{random_label, Bruteforce};
+    use crate::http_client::HttpClient;
+    use crate::{
+        modules::SubdomainModule,
+        testing::{dns_server, local_resolver},
+    };
+    use reqwest::Client;
+
+    fn words() -> Vec<String> {
+        ["www", "mail", "dev", "nothing"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn finds_existing_words() {
+        let dns_resolver = local_resolver(
+            dns_server(vec![
+                ("www.kerkour.com", "127.0.0.1"),
+                ("mail.kerkour.com", "127.0.0.2"),
+                ("kerkour.com", "127.0.0.3"),
+            ])
+            .await,
+        );
+        let module = Bruteforce::new(dns_resolver, words(), 10);
+
+        let mut subdomains = module
+            .enumerate(&HttpClient::from(Client::new()), "kerkour.com")
+            .await
+            .unwrap()
+            .subdomains;
+        subdomains.sort();
+        assert_eq!(vec!["mail.kerkour.com", "www.kerkour.com"], subdomains);
+    }
+
+    #[tokio::test]
+    async fn skips_wildcard_answers() {
+        let dns_resolver = local_resolver(
+            dns_server(vec![
+                ("*.kerkour.com", "127.0.0.1"),
+                ("dev.kerkour.com", "127.0.0.2"),
+            ])
+            .await,
+        );
+        let module = Bruteforce::new(dns_resolver, words(), 10);
+
+        let subdomains = module
+            .enumerate(&HttpClient::from(Client::new()), "kerkour.com")
+            .await
+            .unwrap()
+            .subdomains;
+        assert_eq!(vec!["dev.kerkour.com"], subdomains);
+    }
+
+    #[test]
+    fn random_labels_differ() {
+        assert_ne!(random_label(), random_label());
+    }
+}
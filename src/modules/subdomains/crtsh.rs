@@ -1,5 +1,6 @@
 use crate::{
-    modules::{Module, SubdomainModule},
+    http_client::HttpClient,
+    modules::{Enumeration, Module, SubdomainModule},
     Error,
 };
 use async_trait::async_trait;
@@ -14,6 +15,8 @@ impl Crtsh {
     }
 }
 
+crate::register_subdomains_module!(|_| Crtsh::new());
+
 impl Module for Crtsh {
     fn name(&self) -> String {
         String::from("subdomains/crtsh")
@@ -21,6 +24,14 @@ impl Module for Crtsh {
     fn description(&self) -> String {
         String::from("use crt.sh/ to find subdomains")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["passive"]
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from(
+            "GET https://crt.sh/?q=%25.{domain}&output=json",
+        )]
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -31,12 +42,16 @@ struct CrtShEntry {
 #[async_trait]
 //this is all used in cli
 impl SubdomainModule for Crtsh {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>, Error> {
+    async fn enumerate(
+        &self,
+        http_client: &HttpClient,
+        domain: &str,
+    ) -> Result<Enumeration, Error> {
         //the query
         // returns as json
         let url = format!("https://crt.sh/?q=%25.{}&output=json", domain);
         //makes the query
-        let res = reqwest::get(&url).await?;
+        let res = http_client.get(&url).send().await?;
         //if it fails returns err
         if !res.status().is_success() {
             return Err(Error::InvalidHttpResponse(self.name()));
@@ -49,16 +64,15 @@ impl SubdomainModule for Crtsh {
         // clean entries to a list of subdomains
         let subdomains: HashSet<String> = crtsh_entries
             .into_iter()
-            .map(|entry| {
+            .flat_map(|entry| {
                 entry
                     .name_value
-                    .split("\n")
+                    .split('\n')
                     .map(|subdomain| subdomain.trim().to_string())
                     .collect::<Vec<String>>()
             })
-            .flatten()
-            .filter(|subdomain: &String| !subdomain.contains("*"))
+            .filter(|subdomain: &String| !subdomain.contains('*'))
             .collect();
-        Ok(subdomains.into_iter().collect())
+        Ok(subdomains.into_iter().collect::<Vec<String>>().into())
     }
 }
@@ -0,0 +1,399 @@
+use crate::{
+    dns::Resolver,
+    http_client::HttpClient,
+    modules::{excerpt, Enumeration, Finding, Module, Severity, SubdomainModule},
+    Error,
+};
+use async_trait::async_trait;
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+use trust_dns_resolver::proto::{
+    op::{Message, MessageType, OpCode, Query, ResponseCode},
+    rr::{rdata::SOA, Name, RData, Record, RecordType},
+};
+
+// srv records are not on the domain itself so we ask for the usual services
+const SRV_SERVICES: &[&str] = &[
+    "_sip._tcp",
+    "_sip._udp",
+    "_sips._tcp",
+    "_xmpp-client._tcp",
+    "_xmpp-server._tcp",
+    "_ldap._tcp",
+    "_kerberos._tcp",
+    "_kerberos._udp",
+    "_autodiscover._tcp",
+    "_caldav._tcp",
+    "_carddav._tcp",
+    "_imaps._tcp",
+    "_submission._tcp",
+];
+
+// DnsRecords asks the domain's own nameservers for everything they will give
+// a zone transfer lists the whole zone, other records point to more hosts
+pub struct DnsRecords {
+    dns_resolver: Resolver,
+    // where the nameservers listen, only changes in the tests
+    port: u16,
+    timeout: Duration,
+}
+
+impl DnsRecords {
+    pub fn new(dns_resolver: Resolver) -> Self {
+        DnsRecords {
+            dns_resolver,
+            port: 53,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    // the addresses of every NS of the domain
+    async fn nameservers(&self, domain: &str) -> Vec<(String, IpAddr)> {
+        let names: Vec<String> = match self.dns_resolver.ns_lookup(domain).await {
+            Ok(lookup) => lookup.iter().map(name_text).collect(),
+            Err(err) => {
+                log::debug!("{}: NS {}: {}", self.name(), domain, err);
+                return Vec::new();
+            }
+        };
+        let mut nameservers = Vec::new();
+        for name in names {
+            if let Ok(lookup) = self.dns_resolver.lookup_ip(name.as_str()).await {
+                nameservers.extend(lookup.iter().map(|ip| (name.clone(), ip)));
+            }
+        }
+        nameservers
+    }
+
+    // MX, TXT, SOA and CNAME of the domain plus the usual SRV names
+    async fn harvest(&self, domain: &str) -> Vec<Record> {
+        let mut queries: Vec<(String, RecordType)> = [
+            RecordType::MX,
+            RecordType::TXT,
+            RecordType::SOA,
+            RecordType::CNAME,
+        ]
+        .into_iter()
+        .map(|record_type| (domain.to_string(), record_type))
+        .collect();
+        queries.extend(
+            SRV_SERVICES
+                .iter()
+                .map(|service| (format!("{}.{}", service, domain), RecordType::SRV)),
+        );
+
+        let mut records = Vec::new();
+        for (name, record_type) in queries {
+            let lookup = self.dns_resolver.lookup(name.as_str(), record_type).await;
+            if let Ok(lookup) = lookup {
+                records.extend(lookup.record_iter().cloned());
+            }
+        }
+        records
+    }
+}
+
+crate::register_subdomains_module!(|dns_resolver| DnsRecords::new(dns_resolver.clone()));
+
+impl Module for DnsRecords {
+    fn name(&self) -> String {
+        String::from("subdomains/dns_records")
+    }
+    fn description(&self) -> String {
+        String::from(
+            "try zone transfers on the nameservers and read MX, TXT, SRV, CNAME and SOA records",
+        )
+    }
+    fn tags(&self) -> Vec<&str> {
+        vec!["dns"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Medium)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![
+            String::from("DNS NS {domain} and A/AAAA of each nameserver"),
+            String::from("DNS MX, TXT, SOA, CNAME {domain} and SRV of the usual services"),
+            String::from("AXFR then IXFR {domain} over tcp to each nameserver"),
+        ]
+    }
+}
+
+#[async_trait]
+impl SubdomainModule for DnsRecords {
+    async fn enumerate(
+        &self,
+        _http_client: &HttpClient,
+        domain: &str,
+    ) -> Result<Enumeration, Error> {
+        let mut records = self.harvest(domain).await;
+        let mut findings = Vec::new();
+
+        for (nameserver, ip) in self.nameservers(domain).await {
+            let addr = SocketAddr::new(ip, self.port);
+            // IXFR from serial 0 is answered with the whole zone too
+            for record_type in [RecordType::AXFR, RecordType::IXFR] {
+                let transfer =
+                    timeout(self.timeout, zone_transfer(addr, domain, record_type)).await;
+                match transfer {
+                    Ok(Ok(zone)) => {
+                        findings.push(transfer_finding(
+                            self.name(),
+                            domain,
+                            &nameserver,
+                            addr,
+                            record_type,
+                            &zone,
+                        ));
+                        records.extend(zone);
+                        break;
+                    }
+                    Ok(Err(err)) => {
+                        log::debug!("{}: {} {}: {}", self.name(), record_type, addr, err)
+                    }
+                    Err(_) => log::debug!("{}: {} {}: timeout", self.name(), record_type, addr),
+                }
+            }
+        }
+
+        Ok(Enumeration {
+            subdomains: record_hostnames(&records, domain),
+            findings,
+        })
+    }
+}
+
+// the zone as the nameserver sent it, soa first and last
+async fn zone_transfer(
+    addr: SocketAddr,
+    domain: &str,
+    record_type: RecordType,
+) -> Result<Vec<Record>, Error> {
+    let zone = Name::from_utf8(domain).map_err(|err| Error::Dns(err.to_string()))?;
+    let mut request = Message::new();
+    request
+        .set_id(message_id())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .add_query(Query::query(zone.clone(), record_type));
+    if record_type == RecordType::IXFR {
+        // the serial we claim to have, 0 means everything
+        let soa = SOA::new(zone.clone(), zone.clone(), 0, 0, 0, 0, 0);
+        request.add_name_server(Record::from_rdata(zone, 0, RData::SOA(soa)));
+    }
+    let request = request
+        .to_vec()
+        .map_err(|err| Error::Dns(err.to_string()))?;
+
+    let mut stream = TcpStream::connect(addr).await.map_err(dns_error)?;
+    stream
+        .write_u16(request.len() as u16)
+        .await
+        .map_err(dns_error)?;
+    stream.write_all(&request).await.map_err(dns_error)?;
+
+    // the answer can be split in many messages, it ends with the second soa
+    let mut records: Vec<Record> = Vec::new();
+    loop {
+        let len = stream.read_u16().await.map_err(dns_error)?;
+        let mut buffer = vec![0; len as usize];
+        stream.read_exact(&mut buffer).await.map_err(dns_error)?;
+        let mut response = Message::from_vec(&buffer).map_err(|err| Error::Dns(err.to_string()))?;
+        if response.response_code() != ResponseCode::NoError {
+            return Err(Error::Dns(format!("{} refused", record_type)));
+        }
+        let answers = response.take_answers();
+        if answers.is_empty() {
+            return Err(Error::Dns(format!("{} empty", record_type)));
+        }
+        records.extend(answers);
+        if records.first().map(Record::record_type) != Some(RecordType::SOA) {
+            return Err(Error::Dns(format!(
+                "{} does not start with a SOA",
+                record_type
+            )));
+        }
+        let soas = records
+            .iter()
+            .filter(|record| record.record_type() == RecordType::SOA)
+            .count();
+        if soas >= 2 {
+            return Ok(records);
+        }
+    }
+}
+
+fn transfer_finding(
+    module: String,
+    domain: &str,
+    nameserver: &str,
+    addr: SocketAddr,
+    record_type: RecordType,
+    zone: &[Record],
+) -> Finding {
+    let listing: Vec<String> = zone.iter().map(|record| record.to_string()).collect();
+    Finding::new(
+        module,
+        Severity::Medium,
+        format!("dns://{}/{}?type={}", addr, domain, record_type),
+    )
+    .with_evidence(
+        format!("{} {} @{}", record_type, domain, nameserver),
+        excerpt(&listing.join("\n"), 1000),
+    )
+    .with_remediation("only allow zone transfers to the secondary nameservers")
+}
+
+// every name in the records that belongs to the domain
+fn record_hostnames(records: &[Record], domain: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for record in records {
+        names.push(name_text(record.name()));
+        match record.data() {
+            Some(RData::CNAME(name)) | Some(RData::NS(name)) => names.push(name_text(name)),
+            Some(RData::MX(mx)) => names.push(name_text(mx.exchange())),
+            Some(RData::SRV(srv)) => names.push(name_text(srv.target())),
+            Some(RData::SOA(soa)) => names.push(name_text(soa.mname())),
+            Some(RData::TXT(txt)) => {
+                for data in txt.txt_data() {
+                    names.extend(txt_hostnames(&String::from_utf8_lossy(data)));
+                }
+            }
+            _ => {}
+        }
+    }
+    let suffix = format!(".{}", domain);
+    names
+        .into_iter()
+        .map(|name| name.to_lowercase())
+        // service labels like _sip._tcp are not hosts
+        .filter(|name| !name.contains('*') && !name.starts_with('_'))
+        .filter(|name| name == domain || name.ends_with(&suffix))
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+// spf and verification records: include:mail.kerkour.com, a:smtp.kerkour.com
+fn txt_hostnames(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|token| {
+            token
+                .rsplit([':', '='])
+                .next()
+                .unwrap_or(token)
+                .split('/')
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches('.')
+                .to_string()
+        })
+        .filter(|token| token.contains('.'))
+        .collect()
+}
+
+fn name_text(name: &Name) -> String {
+    name.to_utf8().trim_end_matches('.').to_string()
+}
+
+fn message_id() -> u16 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos() as u16)
+        .unwrap_or_default()
+}
+
+fn dns_error(err: std::io::Error) -> Error {
+    Error::Dns(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{txt_hostnames, DnsRecords};
+    use crate::http_client::HttpClient;
+    use crate::{
+        modules::{Severity, SubdomainModule},
+        testing::{local_resolver, zone_server},
+    };
+    use reqwest::Client;
+
+    fn zone() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("kerkour.com", "SOA ns1.kerkour.com hostmaster.kerkour.com"),
+            ("kerkour.com", "NS ns1.kerkour.com"),
+            ("kerkour.com", "MX 10 mail.kerkour.com"),
+            ("kerkour.com", "TXT v=spf1 include:spf.kerkour.com -all"),
+            ("kerkour.com", "127.0.0.1"),
+            ("ns1.kerkour.com", "127.0.0.1"),
+            ("_sip._tcp.kerkour.com", "SRV 0 5 5060 sip.kerkour.com"),
+            ("secret.kerkour.com", "127.0.0.2"),
+            ("www.kerkour.com", "kerkour.netlify.app"),
+        ]
+    }
+
+    async fn module(allow_transfer: bool) -> DnsRecords {
+        let addr = zone_server(zone(), allow_transfer).await;
+        let mut module = DnsRecords::new(local_resolver(addr));
+        module.port = addr.port();
+        module
+    }
+
+    #[tokio::test]
+    async fn zone_transfer() {
+        let enumeration = module(true)
+            .await
+            .enumerate(&HttpClient::from(Client::new()), "kerkour.com")
+            .await
+            .unwrap();
+
+        assert!(enumeration
+            .subdomains
+            .contains(&String::from("secret.kerkour.com")));
+        assert!(enumeration
+            .subdomains
+            .contains(&String::from("www.kerkour.com")));
+        assert_eq!(1, enumeration.findings.len());
+        let finding = &enumeration.findings[0];
+        assert_eq!(Severity::Medium, finding.severity);
+        assert!(finding.url.ends_with("/kerkour.com?type=AXFR"));
+    }
+
+    #[tokio::test]
+    async fn harvest_records_without_transfer() {
+        let enumeration = module(false)
+            .await
+            .enumerate(&HttpClient::from(Client::new()), "kerkour.com")
+            .await
+            .unwrap();
+        let mut subdomains = enumeration.subdomains;
+        subdomains.sort();
+
+        assert!(enumeration.findings.is_empty());
+        assert_eq!(
+            vec![
+                "kerkour.com",
+                "mail.kerkour.com",
+                "ns1.kerkour.com",
+                "sip.kerkour.com",
+                "spf.kerkour.com",
+            ],
+            subdomains
+        );
+    }
+
+    #[test]
+    fn hostnames_in_txt() {
+        assert_eq!(
+            vec!["_spf.google.com", "mail.kerkour.com"],
+            txt_hostnames("v=spf1 include:_spf.google.com a:mail.kerkour.com/24 ~all")
+        );
+    }
+}
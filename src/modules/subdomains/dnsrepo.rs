@@ -1,5 +1,6 @@
 use crate::{
-    modules::{Module, SubdomainModule},
+    http_client::HttpClient,
+    modules::{Enumeration, Module, SubdomainModule},
     Error,
 };
 use async_trait::async_trait;
@@ -13,6 +14,8 @@ impl Dnsrepo {
     }
 }
 
+crate::register_subdomains_module!(|_| Dnsrepo::new());
+
 impl Module for Dnsrepo {
     fn name(&self) -> String {
         String::from("subdomains/dsrepo")
@@ -20,14 +23,24 @@ impl Module for Dnsrepo {
     fn description(&self) -> String {
         String::from("checks dnsrepo for subdomain")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["passive"]
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET https://dnsrepo.noc.org/?domain={domain}")]
+    }
 }
 
 #[async_trait]
 impl SubdomainModule for Dnsrepo {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>, Error> {
+    async fn enumerate(
+        &self,
+        http_client: &HttpClient,
+        domain: &str,
+    ) -> Result<Enumeration, Error> {
         let url = format!("https://dnsrepo.noc.org/?domain={}", domain);
 
-        let res = reqwest::get(&url).await?;
+        let res = http_client.get(&url).send().await?;
         if !res.status().is_success() {
             return Err(Error::InvalidHttpResponse(self.name()));
         }
@@ -42,6 +55,6 @@ impl SubdomainModule for Dnsrepo {
         }
         subdomain_list.sort();
         subdomain_list.dedup();
-        Ok(subdomain_list.into_iter().collect())
+        Ok(subdomain_list.into_iter().collect::<Vec<String>>().into())
     }
 }
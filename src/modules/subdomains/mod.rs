@@ -1,6 +1,6 @@
+mod bruteforce;
+pub use bruteforce::{read_wordlist, Bruteforce};
 mod crtsh;
-pub use crtsh::Crtsh;
-mod web_archive;
-pub use web_archive::WebArchive;
+mod dns_records;
 mod dnsrepo;
-pub use dnsrepo::Dnsrepo;
+mod web_archive;
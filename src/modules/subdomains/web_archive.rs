@@ -1,5 +1,6 @@
 use crate::{
-    modules::{Module, SubdomainModule},
+    http_client::HttpClient,
+    modules::{Enumeration, Module, SubdomainModule},
     Error,
 };
 use async_trait::async_trait;
@@ -15,6 +16,8 @@ impl WebArchive {
     }
 }
 
+crate::register_subdomains_module!(|_| WebArchive::new());
+
 impl Module for WebArchive {
     fn name(&self) -> String {
         String::from("subdomains/webarchive")
@@ -22,6 +25,14 @@ impl Module for WebArchive {
     fn description(&self) -> String {
         String::from("Use web.archive.org to find subdomains")
     }
+    fn tags(&self) -> Vec<&str> {
+        vec!["passive"]
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![
+            String::from("GET https://web.archive.org/cdx/search/cdx?matchType=domain&fl=original&output=json&collapse=urlkey&url={domain}"),
+        ]
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -29,11 +40,15 @@ struct WebArchiveResponse(Vec<Vec<String>>);
 
 #[async_trait]
 impl SubdomainModule for WebArchive {
-    async fn enumerate(&self, domain: &str) -> Result<Vec<String>, Error> {
+    async fn enumerate(
+        &self,
+        http_client: &HttpClient,
+        domain: &str,
+    ) -> Result<Enumeration, Error> {
         // format query
         let url = format!("https://web.archive.org/cdx/search/cdx?matchType=domain&fl=original&output=json&collapse=urlkey&url={}", domain);
         // gets result & chechks if query fails
-        let res = reqwest::get(&url).await?;
+        let res = http_client.get(&url).send().await?;
         if !res.status().is_success() {
             return Err(Error::InvalidHttpResponse(self.name()));
         }
@@ -51,15 +66,14 @@ impl SubdomainModule for WebArchive {
             .flatten()
             .filter_map(|url| {
                 Url::parse(&url)
-                    .map_err(|err| {
+                    .inspect_err(|err| {
                         log::error!("{}: err parsing url: {}", self.name(), err);
-                        err
                     })
                     .ok()
             })
             .filter_map(|url| url.host_str().map(|host| host.to_string()))
             .collect();
 
-        Ok(subdomains.into_iter().collect())
+        Ok(subdomains.into_iter().collect::<Vec<String>>().into())
     }
 }
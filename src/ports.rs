@@ -1,57 +1,270 @@
 use crate::{
-    common_ports::MOST_COMMON_PORTS,
-    modules::{Port, Subdomain},
+    common_ports::{MOST_COMMON_PORTS, TOP_1000_PORTS},
+    modules::Port,
+    throttle::Throttle,
+    Error,
 };
 
 use futures::{stream, StreamExt};
 use std::{
-    net::{SocketAddr, ToSocketAddrs},
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
     time::Duration,
 };
 use tokio::net::TcpStream;
 
-//?????
-pub async fn scan_ports(concur: usize, mut subdomain: Subdomain) -> Subdomain {
-    //puts the subdomain in the socket_addr
-    let socket_addr: Vec<SocketAddr> = format!("{}:1024", subdomain.domain)
-        .to_socket_addrs()
-        .expect("port scanner:creating sock addr")
-        .collect();
-
-    if socket_addr.len() == 0 {
-        return subdomain;
-    }
-
-    let socket_adr = socket_addr[0];
-    //tests subdomain ports
-    //it tries the list of all the common ports per subdomain
-    subdomain.open_ports = stream::iter(MOST_COMMON_PORTS.into_iter())
-        .map(|port| async move {
-            let port = scan_port(socket_adr, *port).await;
-            if port.is_open {
-                return Some(port);
+// which of the resolved addresses get scanned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    Any,
+    V4,
+    V6,
+}
+
+impl IpVersion {
+    pub fn matches(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpVersion::Any => true,
+            IpVersion::V4 => ip.is_ipv4(),
+            IpVersion::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl FromStr for IpVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(IpVersion::Any),
+            "ipv4" => Ok(IpVersion::V4),
+            "ipv6" => Ok(IpVersion::V6),
+            _ => Err(Error::Config(format!("unknown ip version: {}", s))),
+        }
+    }
+}
+
+// tries every port of the list on the ip
+// with at most concur connections at the same time
+// socks_proxy is only used with the socks feature
+// every connect waits for the throttle like an http request
+pub async fn scan_ports(
+    concur: usize,
+    ports: &[u16],
+    ip: IpAddr,
+    socks_proxy: Option<SocketAddr>,
+    throttle: &Throttle,
+) -> Vec<Port> {
+    let host = ip.to_string();
+    // owned ports, a closure over &u16 makes the scan future not Send for tokio::spawn
+    stream::iter(ports.to_vec())
+        .map(|port| {
+            let host = &host;
+            async move {
+                throttle.wait(host).await;
+                let port = scan_port(SocketAddr::new(ip, port), socks_proxy).await;
+                if port.is_open {
+                    return Some(port);
+                }
+                None
             }
-            None
         })
         .buffer_unordered(concur)
         .filter_map(|port| async { port })
         .collect()
-        .await;
-    subdomain
+        .await
 }
 //creates a stream to the socket addr
 //if it connects return ok and create a vec for the findings
 //later used for vuln scan
-async fn scan_port(mut socket_addr: SocketAddr, port: u16) -> Port {
+#[cfg_attr(not(feature = "socks"), allow(unused_variables))]
+async fn scan_port(socket_addr: SocketAddr, socks_proxy: Option<SocketAddr>) -> Port {
     let timeout = Duration::from_secs(3); //3 secs its closed
-    socket_addr.set_port(port);
-    let is_open = matches!(
-        tokio::time::timeout(timeout, TcpStream::connect(&socket_addr)).await,
-        Ok(Ok(_)),
-    );
+    let connect = async {
+        // the proxy says if it could reach the port
+        #[cfg(feature = "socks")]
+        if let Some(proxy) = socks_proxy {
+            return tokio_socks::tcp::Socks5Stream::connect(proxy, socket_addr)
+                .await
+                .is_ok();
+        }
+        TcpStream::connect(&socket_addr).await.is_ok()
+    };
+    let is_open = matches!(tokio::time::timeout(timeout, connect).await, Ok(true));
     Port {
-        port,
+        port: socket_addr.port(),
+        ip: socket_addr.ip(),
+        service: None,
+        certificate: None,
         is_open,
         findings: Vec::new(),
     }
 }
+
+// nmap like port list: 80,443,8000-8100,top100,top1000,all,default
+// keeps the order so top lists are scanned most common first
+pub fn parse_ports(spec: &str) -> Result<Vec<u16>, Error> {
+    let mut ports = Vec::new();
+    for part in spec.split(',').map(str::trim) {
+        match part {
+            "" => return Err(Error::InvalidPorts(format!("{}: empty port", spec))),
+            "all" => ports.extend(1..=u16::MAX),
+            "default" => ports.extend_from_slice(MOST_COMMON_PORTS),
+            _ if part.starts_with("top") => {
+                let n: usize = part[3..]
+                    .parse()
+                    .map_err(|_| Error::InvalidPorts(part.to_string()))?;
+                if n == 0 || n > TOP_1000_PORTS.len() {
+                    return Err(Error::InvalidPorts(format!(
+                        "{}: only top1 to top{} are known",
+                        part,
+                        TOP_1000_PORTS.len()
+                    )));
+                }
+                ports.extend_from_slice(&TOP_1000_PORTS[..n]);
+            }
+            _ => match part.split_once('-') {
+                // open ends like nmap: -1024 or 60000-
+                Some((start, end)) => {
+                    let start = if start.is_empty() {
+                        1
+                    } else {
+                        parse_port(start)?
+                    };
+                    let end = if end.is_empty() {
+                        u16::MAX
+                    } else {
+                        parse_port(end)?
+                    };
+                    if start > end {
+                        return Err(Error::InvalidPorts(format!("{}: reversed range", part)));
+                    }
+                    ports.extend(start..=end);
+                }
+                None => ports.push(parse_port(part)?),
+            },
+        }
+    }
+
+    let mut seen = HashSet::new();
+    ports.retain(|port| seen.insert(*port));
+    Ok(ports)
+}
+
+fn parse_port(port: &str) -> Result<u16, Error> {
+    match port.trim().parse::<u16>() {
+        Ok(0) => Err(Error::InvalidPorts(String::from(
+            "0: port 0 can't be scanned",
+        ))),
+        Ok(port) => Ok(port),
+        Err(_) => Err(Error::InvalidPorts(port.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_ports;
+    use crate::common_ports::{MOST_COMMON_PORTS, TOP_1000_PORTS};
+
+    #[test]
+    fn parse_ports_list_and_ranges() {
+        assert_eq!(vec![80], parse_ports("80").unwrap());
+        assert_eq!(
+            vec![80, 443, 8000, 8001, 8002],
+            parse_ports("80, 443,8000-8002").unwrap()
+        );
+        assert_eq!(vec![22], parse_ports("22-22").unwrap());
+        assert_eq!(1024, parse_ports("-1024").unwrap().len());
+        assert_eq!(vec![65534, 65535], parse_ports("65534-").unwrap());
+        assert_eq!(65535, parse_ports("all").unwrap().len());
+    }
+
+    #[test]
+    fn parse_ports_top() {
+        assert_eq!(&TOP_1000_PORTS[..100], parse_ports("top100").unwrap());
+        assert_eq!(TOP_1000_PORTS, parse_ports("top1000").unwrap());
+        assert_eq!(MOST_COMMON_PORTS, parse_ports("default").unwrap());
+        assert!(parse_ports("top0").is_err());
+        assert!(parse_ports("top1001").is_err());
+        assert!(parse_ports("topfoo").is_err());
+    }
+
+    #[test]
+    fn parse_ports_dedup() {
+        assert_eq!(vec![80, 81, 443], parse_ports("80,80-81,443,81").unwrap());
+        let top10_and_more = parse_ports("top10,80,8000-8001").unwrap();
+        assert_eq!(&TOP_1000_PORTS[..10], &top10_and_more[..10]);
+        assert_eq!(vec![8000, 8001], top10_and_more[10..].to_vec());
+    }
+
+    #[test]
+    fn parse_ports_invalid() {
+        assert!(parse_ports("0").is_err());
+        assert!(parse_ports("0-80").is_err());
+        assert!(parse_ports("443-80").is_err());
+        assert!(parse_ports("65536").is_err());
+        assert!(parse_ports("80,,443").is_err());
+        assert!(parse_ports("").is_err());
+        assert!(parse_ports("http").is_err());
+        assert!(parse_ports("80-90-100").is_err());
+    }
+}
+
+#[cfg(all(test, feature = "socks"))]
+mod socks_tests {
+    use super::scan_ports;
+    use crate::{testing::mock_server, throttle::Throttle};
+    use std::net::{IpAddr, SocketAddr};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    // socks5 without auth, ipv4 targets only
+    // counts on the client sending each step in one write
+    async fn socks5_server() -> SocketAddr {
+        mock_server(|mut stream| async move {
+            let mut buffer = [0; 262];
+            if stream.read(&mut buffer).await.is_err() || stream.write_all(&[5, 0]).await.is_err() {
+                return;
+            }
+            let n = match stream.read(&mut buffer).await {
+                Ok(n) if n >= 10 && buffer[3] == 1 => n,
+                _ => return,
+            };
+            let ip = IpAddr::from([buffer[4], buffer[5], buffer[6], buffer[7]]);
+            let port = u16::from_be_bytes([buffer[n - 2], buffer[n - 1]]);
+            let status = match TcpStream::connect((ip, port)).await {
+                Ok(_) => 0,
+                Err(_) => 5,
+            };
+            let _ = stream.write_all(&[5, status, 0, 1, 0, 0, 0, 0, 0, 0]).await;
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn scan_through_socks5() {
+        let proxy = socks5_server().await;
+        let open = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open_port = open.local_addr().unwrap().port();
+        let closed_port = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let ports = scan_ports(
+            10,
+            &[open_port, closed_port],
+            "127.0.0.1".parse().unwrap(),
+            Some(proxy),
+            &Throttle::unlimited(),
+        )
+        .await;
+        assert_eq!(
+            vec![open_port],
+            ports.iter().map(|port| port.port).collect::<Vec<u16>>()
+        );
+    }
+}
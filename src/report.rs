@@ -0,0 +1,369 @@
+use crate::{
+    dns::DnsRecord,
+    modules::{Finding, Port, Subdomain},
+    scanner::Event as ScanEvent,
+    services::{Service, TlsCertificate},
+    Error,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    io::{self, Write},
+    net::IpAddr,
+    str::FromStr,
+    sync::Mutex,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    // human readable, what tricoder always printed
+    Text,
+    // one report at the end of the scan
+    Json,
+    // one event per line as soon as we have it
+    Jsonl,
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            _ => Err(Error::InvalidOutputFormat(s.to_string())),
+        }
+    }
+}
+
+// everything we know about a target once the scan is done
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub targets: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub subdomains: Vec<Subdomain>,
+}
+
+// a line of the jsonl output
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    ScanStarted {
+        targets: &'a [String],
+        started_at: DateTime<Utc>,
+    },
+    Subdomain {
+        domain: &'a str,
+        addresses: &'a [IpAddr],
+        cnames: &'a [String],
+        records: &'a [DnsRecord],
+    },
+    Port {
+        domain: &'a str,
+        port: u16,
+        ip: IpAddr,
+        service: &'a Option<Service>,
+        certificate: &'a Option<TlsCertificate>,
+    },
+    Finding {
+        domain: &'a str,
+        // none when the finding is about the domain itself
+        #[serde(skip_serializing_if = "Option::is_none")]
+        port: Option<u16>,
+        #[serde(flatten)]
+        finding: &'a Finding,
+    },
+    ScanCompleted {
+        targets: &'a [String],
+        finished_at: DateTime<Utc>,
+    },
+}
+
+// writes the results of a scan in the chosen format
+pub struct Reporter {
+    format: OutputFormat,
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Reporter {
+    pub fn new(format: OutputFormat, out: Box<dyn Write + Send>) -> Self {
+        Reporter {
+            format,
+            out: Mutex::new(out),
+        }
+    }
+
+    pub fn stdout(format: OutputFormat) -> Self {
+        Reporter::new(format, Box::new(io::stdout()))
+    }
+
+    // what the cli shows of the events of a scan
+    pub fn report(&self, event: &ScanEvent) {
+        match event {
+            ScanEvent::Started {
+                targets,
+                started_at,
+            } => self.started(targets, *started_at),
+            ScanEvent::SubdomainFound { domain, source } => {
+                log::debug!("{}: found {}", source, domain)
+            }
+            ScanEvent::HostResolved(subdomain) => self.resolved(subdomain),
+            ScanEvent::PortOpen { domain, port } => self.port_open(domain, port),
+            ScanEvent::HostScanned(subdomain) => self.scanned(subdomain),
+            ScanEvent::PortsScanned { depth } => {
+                if *depth == 0 && self.format == OutputFormat::Text {
+                    self.write("-----------------vuln---------------------\n");
+                }
+            }
+            ScanEvent::Finding {
+                domain,
+                port,
+                finding,
+            } => self.finding(domain, *port, finding),
+            // the http modules fail on every port that does not speak http, not worth more
+            ScanEvent::ModuleError {
+                module,
+                target,
+                error,
+            } if module.starts_with("http/") => log::debug!("{}: {}: {}", module, target, error),
+            ScanEvent::ModuleError {
+                module,
+                target,
+                error,
+            } => log::error!("{}: {}: {}", module, target, error),
+            ScanEvent::Completed(report) => self.finished(report),
+        }
+    }
+
+    pub fn started(&self, targets: &[String], started_at: DateTime<Utc>) {
+        if self.format == OutputFormat::Jsonl {
+            self.event(&Event::ScanStarted {
+                targets,
+                started_at,
+            });
+        }
+    }
+
+    // the subdomain resolves
+    pub fn resolved(&self, subdomain: &Subdomain) {
+        if self.format == OutputFormat::Jsonl {
+            self.event(&Event::Subdomain {
+                domain: &subdomain.domain,
+                addresses: &subdomain.addresses,
+                cnames: &subdomain.cnames,
+                records: &subdomain.records,
+            });
+        }
+    }
+
+    pub fn port_open(&self, domain: &str, port: &Port) {
+        if self.format == OutputFormat::Jsonl {
+            self.event(&Event::Port {
+                domain,
+                port: port.port,
+                ip: port.ip,
+                service: &port.service,
+                certificate: &port.certificate,
+            });
+        }
+    }
+
+    // the ports of the subdomain were scanned
+    pub fn scanned(&self, subdomain: &Subdomain) {
+        if self.format != OutputFormat::Text {
+            return;
+        }
+        let mut text = format!("{}\n", subdomain.domain);
+        for record in &subdomain.records {
+            text.push_str(&format!(
+                "  {} {} ({}s)\n",
+                record.record_type, record.value, record.ttl
+            ));
+        }
+        for port in &subdomain.open_ports {
+            text.push_str(&format!("  {} ({})", port.port, port.ip));
+            if let Some(service) = &port.service {
+                text.push_str(&format!(" {}", service_text(service)));
+            }
+            text.push('\n');
+            if let Some(certificate) = &port.certificate {
+                text.push_str(&format!("    {}\n", certificate_text(certificate)));
+            }
+        }
+        self.write(&text);
+    }
+
+    pub fn finding(&self, domain: &str, port: Option<u16>, finding: &Finding) {
+        match self.format {
+            OutputFormat::Text => self.write(&finding_text(finding)),
+            OutputFormat::Jsonl => self.event(&Event::Finding {
+                domain,
+                port,
+                finding,
+            }),
+            OutputFormat::Json => {}
+        }
+    }
+
+    pub fn finished(&self, report: &Report) {
+        match self.format {
+            OutputFormat::Text => {}
+            OutputFormat::Json => match serde_json::to_string_pretty(report) {
+                Ok(json) => self.write(&format!("{}\n", json)),
+                Err(err) => log::error!("report: serializing: {}", err),
+            },
+            OutputFormat::Jsonl => self.event(&Event::ScanCompleted {
+                targets: &report.targets,
+                finished_at: report.finished_at,
+            }),
+        }
+    }
+
+    fn event(&self, event: &Event) {
+        match serde_json::to_string(event) {
+            Ok(line) => self.write(&format!("{}\n", line)),
+            Err(err) => log::error!("report: serializing event: {}", err),
+        }
+    }
+
+    // flushes every time so jsonl can be piped while the scan runs
+    fn write(&self, text: &str) {
+        let mut out = self.out.lock().expect("report: locking output");
+        if let Err(err) = out.write_all(text.as_bytes()).and_then(|_| out.flush()) {
+            log::error!("report: writing output: {}", err);
+        }
+    }
+}
+
+// http nginx 1.18.0
+fn service_text(service: &Service) -> String {
+    [
+        Some(service.protocol.to_string()),
+        service.product.clone(),
+        service.version.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+// CN=kerkour.com issued by CN=R3, expires 2023-01-01, sans: kerkour.com, www.kerkour.com
+fn certificate_text(certificate: &TlsCertificate) -> String {
+    let mut text = format!("{} issued by {}", certificate.subject, certificate.issuer);
+    if let Some(not_after) = certificate.not_after {
+        text.push_str(&format!(", expires {}", not_after.format("%Y-%m-%d")));
+    }
+    if !certificate.sans.is_empty() {
+        text.push_str(&format!(", sans: {}", certificate.sans.join(", ")));
+    }
+    text
+}
+
+// one line per finding and the details indented under it
+fn finding_text(finding: &Finding) -> String {
+    let mut text = format!(
+        "[{}] {}: {}\n",
+        finding.severity, finding.module, finding.url
+    );
+    if let Some(evidence) = &finding.evidence {
+        text.push_str(&format!("  request: {}\n", evidence.request));
+        text.push_str(&format!("  evidence: {}\n", evidence.response));
+    }
+    if !finding.cves.is_empty() {
+        text.push_str(&format!("  cves: {}\n", finding.cves.join(", ")));
+    }
+    if let Some(remediation) = &finding.remediation {
+        text.push_str(&format!("  remediation: {}\n", remediation));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{finding_text, OutputFormat, Reporter};
+    use crate::modules::{Finding, Port, Severity, Subdomain};
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn jsonl_one_event_per_line() {
+        let buffer = Buffer::default();
+        let reporter = Reporter::new(OutputFormat::Jsonl, Box::new(buffer.clone()));
+
+        let mut subdomain = Subdomain::new(String::from("kerkour.com"));
+        subdomain.addresses = vec!["127.0.0.1".parse().unwrap()];
+        subdomain.open_ports = vec![Port {
+            port: 80,
+            ip: "127.0.0.1".parse().unwrap(),
+            service: None,
+            certificate: None,
+            is_open: true,
+            findings: Vec::new(),
+        }];
+        let finding = Finding::new(
+            String::from("http/dotenv"),
+            Severity::High,
+            String::from("http://kerkour.com:80/.env"),
+        )
+        .with_evidence(
+            String::from("GET http://kerkour.com:80/.env"),
+            String::from("keys: APP_KEY"),
+        );
+
+        reporter.resolved(&subdomain);
+        reporter.port_open("kerkour.com", &subdomain.open_ports[0]);
+        reporter.scanned(&subdomain);
+        reporter.finding("kerkour.com", Some(80), &finding);
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(3, events.len());
+        assert_eq!("subdomain", events[0]["event"]);
+        assert_eq!("127.0.0.1", events[0]["addresses"][0]);
+        assert_eq!("port", events[1]["event"]);
+        assert_eq!(80, events[1]["port"]);
+        assert_eq!("127.0.0.1", events[1]["ip"]);
+        assert_eq!("finding", events[2]["event"]);
+        assert_eq!("http/dotenv", events[2]["module"]);
+        assert_eq!("http://kerkour.com:80/.env", events[2]["url"]);
+        assert_eq!("high", events[2]["severity"]);
+        assert_eq!("keys: APP_KEY", events[2]["evidence"]["response"]);
+        assert!(events[2]["found_at"].is_string());
+    }
+
+    #[test]
+    fn finding_as_text() {
+        let finding = Finding::new(
+            String::from("http/cve_2018_7600"),
+            Severity::Critical,
+            String::from("http://kerkour.com:80/"),
+        )
+        .with_cve("CVE-2018-7600")
+        .with_remediation("Upgrade Drupal");
+
+        assert_eq!(
+            "[critical] http/cve_2018_7600: http://kerkour.com:80/\n  cves: CVE-2018-7600\n  remediation: Upgrade Drupal\n",
+            finding_text(&finding)
+        );
+    }
+}
@@ -0,0 +1,44 @@
+use reqwest::{header, Response, StatusCode};
+use std::time::Duration;
+
+// how many times a request is sent again when the server is overloaded
+// and how long to wait in between, see HttpClient for the sending
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub retries: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            retries: 3,
+            base: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Backoff {
+    // doubles every attempt, the server knows better when it sends Retry-After
+    pub fn delay(&self, attempt: u32, res: &Response) -> Duration {
+        let retry_after = res
+            .headers()
+            .get(header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let delay = retry_after.unwrap_or_else(|| self.base * 2u32.saturating_pow(attempt));
+        delay.min(self.max)
+    }
+}
+
+pub fn should_retry(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+// the host asks us to slow down
+pub fn overloaded(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE
+}
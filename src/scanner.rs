@@ -0,0 +1,1044 @@
+use crate::{
+    common_ports::MOST_COMMON_PORTS,
+    discovery,
+    dns::{self, Resolver},
+    http_client::HttpClient,
+    modules::{
+        self, Enumeration, Finding, HttpModule, ModuleFilter, Port, Subdomain, SubdomainModule,
+    },
+    ports::{self, IpVersion},
+    report::Report,
+    services::{self, Protocol},
+    takeover::{self, Fingerprint, Takeover},
+    targets::Target,
+    throttle::{Limits, Throttle},
+    Error,
+};
+use chrono::{DateTime, Utc};
+use futures::{future::BoxFuture, stream, FutureExt, Stream, StreamExt};
+use reqwest::{redirect::Policy, Client};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+// what a scan tells as it goes, in about this order for every host
+// the findings come as soon as a module has one
+#[derive(Debug, Clone)]
+pub enum Event {
+    Started {
+        targets: Vec<String>,
+        started_at: DateTime<Utc>,
+    },
+    // a name from a subdomain module (source is its name) or from the discovery
+    // it may be out of scope or not resolve, those are dropped after
+    SubdomainFound {
+        domain: String,
+        source: String,
+    },
+    // the name resolves, addresses, cnames and records are filled
+    HostResolved(Subdomain),
+    PortOpen {
+        domain: String,
+        port: Port,
+    },
+    // every address of the host was scanned, open_ports is filled
+    HostScanned(Subdomain),
+    // the port scan of a round is done and the http modules start
+    // depth 0 is the targets and their subdomains, then the names found while scanning
+    PortsScanned {
+        depth: usize,
+    },
+    Finding {
+        domain: String,
+        // none when the finding is about the domain itself
+        port: Option<u16>,
+        finding: Finding,
+    },
+    // the scan goes on without what the module would have found
+    ModuleError {
+        module: String,
+        // the domain or the url it was scanning
+        target: String,
+        error: Error,
+    },
+    // always the last one
+    Completed(Report),
+}
+
+type Events = mpsc::UnboundedSender<Event>;
+
+// the receiver is gone when the stream was dropped, nothing to do then
+fn send(events: &Events, event: Event) {
+    let _ = events.send(event);
+}
+
+// how many things each stage does at the same time
+#[derive(Debug, Clone, Copy)]
+pub struct Concurrency {
+    // subdomain modules running
+    pub subdomains: usize,
+    // dns queries in flight, bruteforce included
+    pub dns: usize,
+    // connections per scanned ip
+    pub ports: usize,
+    // http modules, takeover checks and crawls running
+    pub http: usize,
+}
+
+impl Default for Concurrency {
+    fn default() -> Self {
+        Concurrency {
+            subdomains: 20,
+            dns: 100,
+            ports: 200,
+            http: 20,
+        }
+    }
+}
+
+// let scanner = Scanner::builder().targets(targets).limits(limits).build()?;
+// let mut events = scanner.scan();
+// while let Some(event) = events.next().await { ... }
+pub struct Scanner {
+    targets: Vec<Target>,
+    ports: Vec<u16>,
+    ip_version: IpVersion,
+    service_detection: bool,
+    max_depth: usize,
+    // only the ones the filter allows
+    subdomains_modules: Vec<Box<dyn SubdomainModule>>,
+    http_modules: Vec<Arc<dyn HttpModule>>,
+    // none when filtered out
+    takeover: Option<Takeover>,
+    resolver: Resolver,
+    http_client: HttpClient,
+    crawl_client: HttpClient,
+    subdomains_client: HttpClient,
+    throttle: Arc<Throttle>,
+    socks_proxy: Option<SocketAddr>,
+    concurrency: Concurrency,
+    subdomains_timeout: Duration,
+    probe_timeout: Duration,
+}
+
+// everything has the defaults of the cli but the targets
+pub struct ScannerBuilder {
+    targets: Vec<Target>,
+    ports: Vec<u16>,
+    ip_version: IpVersion,
+    service_detection: bool,
+    max_depth: usize,
+    wordlist: Option<Vec<String>>,
+    http_modules: Option<Vec<Arc<dyn HttpModule>>>,
+    module_filter: ModuleFilter,
+    takeover_fingerprints: Option<Vec<Fingerprint>>,
+    client: Option<Client>,
+    crawl_client: Option<Client>,
+    subdomains_client: Option<Client>,
+    resolver: Option<Resolver>,
+    limits: Limits,
+    concurrency: Concurrency,
+    subdomains_timeout: Duration,
+    probe_timeout: Duration,
+    socks_proxy: Option<SocketAddr>,
+}
+
+impl Scanner {
+    pub fn builder() -> ScannerBuilder {
+        ScannerBuilder {
+            targets: Vec::new(),
+            ports: MOST_COMMON_PORTS.to_vec(),
+            ip_version: IpVersion::Any,
+            service_detection: false,
+            max_depth: 1,
+            wordlist: None,
+            http_modules: None,
+            module_filter: ModuleFilter::default(),
+            takeover_fingerprints: None,
+            client: None,
+            crawl_client: None,
+            subdomains_client: None,
+            resolver: None,
+            limits: Limits::default(),
+            concurrency: Concurrency::default(),
+            subdomains_timeout: Duration::from_secs(120),
+            probe_timeout: Duration::from_secs(3),
+            socks_proxy: None,
+        }
+    }
+
+    // runs the scan on the current tokio runtime
+    // dropping the stream stops the scan
+    pub fn scan(self) -> ScanStream {
+        let (events, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(self.run(events));
+        ScanStream { receiver, task }
+    }
+
+    async fn run(self, events: Events) {
+        let Scanner {
+            targets,
+            ports,
+            ip_version,
+            service_detection,
+            max_depth,
+            subdomains_modules,
+            http_modules,
+            takeover,
+            resolver,
+            http_client,
+            crawl_client,
+            subdomains_client,
+            throttle,
+            socks_proxy,
+            concurrency,
+            subdomains_timeout,
+            probe_timeout,
+        } = self;
+        let targets_names: Vec<String> = targets.iter().map(|target| target.to_string()).collect();
+        log::info!("scanning:{}", targets_names.join(","));
+        let started_at = Utc::now();
+        send(
+            &events,
+            Event::Started {
+                targets: targets_names.clone(),
+                started_at,
+            },
+        );
+
+        // uses modules to enumarete subdomains of every domain target
+        // ips have no subdomains so they skip this
+        // owned jobs, closures taking references make the future not Send for tokio::spawn
+        let jobs: Vec<(usize, String)> = targets
+            .iter()
+            .filter_map(|target| match target {
+                Target::Domain(domain) => Some(domain.clone()),
+                Target::Ip(_) => None,
+            })
+            .flat_map(|domain| {
+                (0..subdomains_modules.len()).map(move |index| (index, domain.clone()))
+            })
+            .collect();
+        let enumerations = enumerate_subdomains(
+            &subdomains_modules,
+            jobs,
+            &subdomains_client,
+            subdomains_timeout,
+            concurrency.subdomains,
+            &events,
+        )
+        .await;
+        let mut subdomains: Vec<String> = Vec::new();
+        // findings about the domain targets, attached to them at the end
+        let mut domain_findings: HashMap<String, Vec<Finding>> = HashMap::new();
+        for (domain, enumeration) in enumerations {
+            subdomains.extend(enumeration.subdomains);
+            for finding in enumeration.findings {
+                send(
+                    &events,
+                    Event::Finding {
+                        domain: domain.clone(),
+                        port: None,
+                        finding: finding.clone(),
+                    },
+                );
+                domain_findings
+                    .entry(domain.clone())
+                    .or_default()
+                    .push(finding);
+            }
+        }
+        // the targets themselves are scanned too
+        subdomains.extend(targets_names.iter().cloned());
+        // dedup across all targets and drop what is out of scope
+        let mut known_domains: HashSet<String> = subdomains
+            .into_iter()
+            .map(|subdomain| subdomain.trim_end_matches('.').to_lowercase())
+            .filter(|subdomain| targets.iter().any(|target| target.in_scope(subdomain)))
+            .collect();
+        let mut pending: Vec<Subdomain> =
+            known_domains.iter().cloned().map(Subdomain::new).collect();
+        log::info!("Found {} domains", pending.len());
+
+        let mut port_stage = PortStage {
+            ports: &ports,
+            ip_version,
+            service_detection,
+            probe_timeout,
+            concur: concurrency.ports,
+            socks_proxy,
+            throttle,
+            scanned_ips: HashMap::new(),
+        };
+        let mut subdomains: Vec<Subdomain> = Vec::new();
+        // hostnames found while scanning (tls sans, cnames, redirects, links)
+        // go through the whole pipeline again until max_depth
+        for depth in 0..=max_depth {
+            let resolved = resolve_subdomains(&resolver, pending, concurrency.dns).await;
+            for subdomain in &resolved {
+                send(&events, Event::HostResolved(subdomain.clone()));
+            }
+            // cnames to services that forgot about them
+            let resolved = match &takeover {
+                Some(takeover) => {
+                    check_takeovers(takeover, &http_client, resolved, concurrency.http, &events)
+                        .await
+                }
+                None => resolved,
+            };
+            let scanned = port_stage.scan(resolved, &events).await;
+            send(&events, Event::PortsScanned { depth });
+            let round_start = subdomains.len();
+            subdomains.extend(scanned);
+            let round = &mut subdomains[round_start..];
+
+            // uses http modules to scan for vulns
+            let round_targets = http_targets(round, &http_modules);
+            let mut names = crawl_targets(&crawl_client, &round_targets, concurrency.http).await;
+            scan_http_targets(
+                round,
+                round_targets,
+                &http_client,
+                concurrency.http,
+                &events,
+            )
+            .await;
+
+            if depth == max_depth {
+                break;
+            }
+            names.extend(discovery::scan_results_hostnames(round));
+            let new_domains = discovery::new_domains(names, &targets, &known_domains);
+            if new_domains.is_empty() {
+                break;
+            }
+            log::info!("Found {} new domains while scanning", new_domains.len());
+            for domain in &new_domains {
+                send(
+                    &events,
+                    Event::SubdomainFound {
+                        domain: domain.clone(),
+                        source: String::from("discovery"),
+                    },
+                );
+            }
+            known_domains.extend(new_domains.iter().cloned());
+            pending = new_domains.into_iter().map(Subdomain::new).collect();
+        }
+        // a domain with findings stays in the report even if it does not resolve
+        for (domain, findings) in domain_findings {
+            match subdomains
+                .iter_mut()
+                .find(|subdomain| subdomain.domain == domain)
+            {
+                Some(subdomain) => subdomain.findings.extend(findings),
+                None => {
+                    let mut subdomain = Subdomain::new(domain);
+                    subdomain.findings = findings;
+                    subdomains.push(subdomain);
+                }
+            }
+        }
+
+        send(
+            &events,
+            Event::Completed(Report {
+                targets: targets_names,
+                started_at,
+                finished_at: Utc::now(),
+                subdomains,
+            }),
+        );
+    }
+}
+
+impl ScannerBuilder {
+    pub fn targets(mut self, targets: Vec<Target>) -> Self {
+        self.targets = targets;
+        self
+    }
+
+    pub fn ports(mut self, ports: Vec<u16>) -> Self {
+        self.ports = ports;
+        self
+    }
+
+    pub fn ip_version(mut self, ip_version: IpVersion) -> Self {
+        self.ip_version = ip_version;
+        self
+    }
+
+    pub fn service_detection(mut self, service_detection: bool) -> Self {
+        self.service_detection = service_detection;
+        self
+    }
+
+    // how many times hostnames found while scanning are fed back
+    pub fn depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    // turns subdomains/bruteforce on
+    pub fn wordlist(mut self, words: Vec<String>) -> Self {
+        self.wordlist = Some(words);
+        self
+    }
+
+    // replaces modules::all_http_modules, the filter still applies
+    pub fn http_modules(mut self, http_modules: Vec<Arc<dyn HttpModule>>) -> Self {
+        self.http_modules = Some(http_modules);
+        self
+    }
+
+    pub fn modules(mut self, module_filter: ModuleFilter) -> Self {
+        self.module_filter = module_filter;
+        self
+    }
+
+    pub fn takeover_fingerprints(mut self, fingerprints: Vec<Fingerprint>) -> Self {
+        self.takeover_fingerprints = Some(fingerprints);
+        self
+    }
+
+    // for the requests to the targets, headers, proxy and timeout included
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    // same as client but must not follow redirects, see discovery::crawl
+    pub fn crawl_client(mut self, client: Client) -> Self {
+        self.crawl_client = Some(client);
+        self
+    }
+
+    // for the third party apis of the subdomain modules
+    pub fn subdomains_client(mut self, client: Client) -> Self {
+        self.subdomains_client = Some(client);
+        self
+    }
+
+    pub fn resolver(mut self, resolver: Resolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    // shared by the three clients and the port scan
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: Concurrency) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn subdomains_timeout(mut self, timeout: Duration) -> Self {
+        self.subdomains_timeout = timeout;
+        self
+    }
+
+    pub fn probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    // where the port scan connects go, needs the socks feature
+    pub fn socks_proxy(mut self, socks_proxy: Option<SocketAddr>) -> Self {
+        self.socks_proxy = socks_proxy;
+        self
+    }
+
+    pub fn build(self) -> Result<Scanner, Error> {
+        if self.targets.is_empty() {
+            return Err(Error::Config(String::from("no targets to scan")));
+        }
+        let module_filter = self.module_filter;
+        let resolver = self.resolver.unwrap_or_default();
+
+        let client = match self.client {
+            Some(client) => client,
+            None => default_client().build()?,
+        };
+        let crawl_client = match self.crawl_client {
+            Some(client) => client,
+            None => default_client().redirect(Policy::none()).build()?,
+        };
+        // the third party apis can be slow to answer
+        // a whole module is still cut at subdomains_timeout
+        let subdomains_client = match self.subdomains_client {
+            Some(client) => client,
+            None => Client::builder().timeout(Duration::from_secs(60)).build()?,
+        };
+        // one throttle for all of them so the limits hold across the clients
+        let throttle = Arc::new(Throttle::new(self.limits));
+
+        let mut subdomains_modules = modules::all_subdomains_modules(&resolver);
+        if let Some(words) = self.wordlist {
+            subdomains_modules.push(Box::new(modules::Bruteforce::new(
+                resolver.clone(),
+                words,
+                self.concurrency.dns,
+            )));
+        }
+        subdomains_modules.retain(|module| module_filter.allows(module.as_ref()));
+        let http_modules = self
+            .http_modules
+            .unwrap_or_else(modules::all_http_modules)
+            .into_iter()
+            .filter(|module| module_filter.allows(module.as_ref()))
+            .collect();
+        let takeover = Takeover::new(
+            self.takeover_fingerprints
+                .unwrap_or_else(takeover::default_fingerprints),
+        );
+
+        Ok(Scanner {
+            targets: self.targets,
+            ports: self.ports,
+            ip_version: self.ip_version,
+            service_detection: self.service_detection,
+            max_depth: self.max_depth,
+            subdomains_modules,
+            http_modules,
+            takeover: module_filter.allows(&takeover).then_some(takeover),
+            resolver,
+            http_client: HttpClient::new(client, throttle.clone()),
+            crawl_client: HttpClient::new(crawl_client, throttle.clone()),
+            subdomains_client: HttpClient::new(subdomains_client, throttle.clone())
+                .retry_server_errors(),
+            throttle,
+            socks_proxy: self.socks_proxy,
+            concurrency: self.concurrency,
+            subdomains_timeout: self.subdomains_timeout,
+            probe_timeout: self.probe_timeout,
+        })
+    }
+}
+
+// what the cli does without flags
+fn default_client() -> reqwest::ClientBuilder {
+    Client::builder()
+        .danger_accept_invalid_certs(true)
+        .timeout(Duration::from_secs(10))
+}
+
+// the events of a running scan
+pub struct ScanStream {
+    receiver: mpsc::UnboundedReceiver<Event>,
+    task: JoinHandle<()>,
+}
+
+impl Stream for ScanStream {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+impl Drop for ScanStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// runs every (module, domain) job, with the timeout for each
+// boxed, the closure over the references is not Send for tokio::spawn otherwise
+fn enumerate_subdomains<'a>(
+    modules: &'a [Box<dyn SubdomainModule>],
+    jobs: Vec<(usize, String)>,
+    subdomains_client: &'a HttpClient,
+    subdomains_timeout: Duration,
+    concur: usize,
+    events: &'a Events,
+) -> BoxFuture<'a, Vec<(String, Enumeration)>> {
+    stream::iter(jobs)
+        .map(move |(index, domain)| {
+            let module = &modules[index];
+            async move {
+                let enumeration = tokio::time::timeout(
+                    subdomains_timeout,
+                    module.enumerate(subdomains_client, &domain),
+                )
+                .await
+                .unwrap_or_else(|_| Err(Error::Timeout(format!("{:?}", subdomains_timeout))));
+                match enumeration {
+                    Ok(enumeration) => {
+                        for subdomain in &enumeration.subdomains {
+                            send(
+                                events,
+                                Event::SubdomainFound {
+                                    domain: subdomain.clone(),
+                                    source: module.name(),
+                                },
+                            );
+                        }
+                        Some((domain, enumeration))
+                    }
+                    Err(error) => {
+                        send(
+                            events,
+                            Event::ModuleError {
+                                module: module.name(),
+                                target: domain,
+                                error,
+                            },
+                        );
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(concur)
+        .filter_map(|domain| async { domain })
+        .collect()
+        .boxed()
+}
+
+// boxed like enumerate_subdomains
+fn check_takeovers<'a>(
+    takeover: &'a Takeover,
+    http_client: &'a HttpClient,
+    subdomains: Vec<Subdomain>,
+    concur: usize,
+    events: &'a Events,
+) -> BoxFuture<'a, Vec<Subdomain>> {
+    stream::iter(subdomains)
+        .map(move |mut subdomain| async move {
+            if let Some(finding) = takeover.check(http_client, &subdomain).await {
+                send(
+                    events,
+                    Event::Finding {
+                        domain: subdomain.domain.clone(),
+                        port: None,
+                        finding: finding.clone(),
+                    },
+                );
+                subdomain.findings.push(finding);
+            }
+            subdomain
+        })
+        .buffer_unordered(concur)
+        .collect()
+        .boxed()
+}
+
+// drops the subdomains that dont resolve
+async fn resolve_subdomains(
+    dns_resolver: &dns::Resolver,
+    subdomains: Vec<Subdomain>,
+    dns_concur: usize,
+) -> Vec<Subdomain> {
+    stream::iter(subdomains)
+        .map(|domain| dns::resolves(dns_resolver, domain))
+        .buffer_unordered(dns_concur)
+        .filter_map(|domain| async move { domain })
+        .collect()
+        .await
+}
+
+// the port scan and what runs after it on the open ports
+// scanned_ips is kept between rounds so an ip is only scanned once
+struct PortStage<'a> {
+    ports: &'a [u16],
+    ip_version: IpVersion,
+    service_detection: bool,
+    probe_timeout: Duration,
+    concur: usize,
+    socks_proxy: Option<SocketAddr>,
+    throttle: Arc<Throttle>,
+    scanned_ips: HashMap<IpAddr, Vec<Port>>,
+}
+
+impl PortStage<'_> {
+    // fills open_ports for every subdomain with the ports of all its addresses
+    // one ip at a time, concur connections each
+    // then looks at what runs on them: services if asked and tls certificates
+    // boxed like enumerate_subdomains
+    fn scan<'s>(
+        &'s mut self,
+        mut subdomains: Vec<Subdomain>,
+        events: &'s Events,
+    ) -> BoxFuture<'s, Vec<Subdomain>> {
+        async move {
+            for subdomain in &mut subdomains {
+                for ip in subdomain.addresses.clone() {
+                    if !self.ip_version.matches(&ip) {
+                        continue;
+                    }
+                    if let Entry::Vacant(entry) = self.scanned_ips.entry(ip) {
+                        let mut open_ports = ports::scan_ports(
+                            self.concur,
+                            self.ports,
+                            ip,
+                            self.socks_proxy,
+                            &self.throttle,
+                        )
+                        .await;
+                        if self.service_detection {
+                            open_ports = services::detect_services(
+                                self.concur,
+                                &subdomain.domain,
+                                self.probe_timeout,
+                                open_ports,
+                            )
+                            .await;
+                        }
+                        open_ports = services::fetch_certificates(
+                            self.concur,
+                            &subdomain.domain,
+                            self.probe_timeout,
+                            open_ports,
+                        )
+                        .await;
+                        entry.insert(open_ports);
+                    }
+                    subdomain
+                        .open_ports
+                        .extend(self.scanned_ips[&ip].iter().cloned());
+                }
+                for port in &subdomain.open_ports {
+                    send(
+                        events,
+                        Event::PortOpen {
+                            domain: subdomain.domain.clone(),
+                            port: port.clone(),
+                        },
+                    );
+                }
+                send(events, Event::HostScanned(subdomain.clone()));
+            }
+            subdomains
+        }
+        .boxed()
+    }
+}
+
+// index of the subdomain, index of the port, module, url
+type HttpTarget = (usize, usize, Arc<dyn HttpModule>, String);
+
+// iter the targets testing the exploit modules
+// findings are sent as they come and kept on their port
+// boxed like enumerate_subdomains
+fn scan_http_targets<'a>(
+    subdomains: &'a mut [Subdomain],
+    targets: Vec<HttpTarget>,
+    http_client: &'a HttpClient,
+    vuln_concur: usize,
+    events: &'a Events,
+) -> BoxFuture<'a, ()> {
+    async move {
+        let mut findings = stream::iter(targets)
+            .map(|(subdomain_index, port_index, module, target)| async move {
+                match module.scan(http_client, &target).await {
+                    Ok(Some(finding)) => Some((subdomain_index, port_index, finding)),
+                    Ok(None) => None,
+                    Err(error) => {
+                        send(
+                            events,
+                            Event::ModuleError {
+                                module: module.name(),
+                                target,
+                                error,
+                            },
+                        );
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(vuln_concur)
+            .filter_map(|finding| async { finding })
+            .boxed();
+        while let Some((subdomain_index, port_index, finding)) = findings.next().await {
+            let subdomain = &mut subdomains[subdomain_index];
+            let port = &mut subdomain.open_ports[port_index];
+            send(
+                events,
+                Event::Finding {
+                    domain: subdomain.domain.clone(),
+                    port: Some(port.port),
+                    finding: finding.clone(),
+                },
+            );
+            port.findings.push(finding);
+        }
+    }
+    .boxed()
+}
+
+// the hostnames every http url of the targets redirects or links to
+// boxed like enumerate_subdomains
+fn crawl_targets<'a>(
+    crawl_client: &'a HttpClient,
+    targets: &[HttpTarget],
+    concur: usize,
+) -> BoxFuture<'a, Vec<String>> {
+    let urls: HashSet<String> = targets.iter().map(|(_, _, _, url)| url.clone()).collect();
+    stream::iter(urls)
+        .map(move |url| async move {
+            discovery::crawl(crawl_client, &url)
+                .await
+                .map_err(|err| log::debug!("crawling {}: {}", url, err))
+                .unwrap_or_default()
+        })
+        .buffer_unordered(concur)
+        .concat()
+        .boxed()
+}
+
+// tranforms subdomains/ports in a target & link it with an http module for exploitation
+// a port open on several ips of the subdomain is the same url so it's only scanned once
+// when we know the service only http(s) ports are kept
+// otherwise ports with a tls certificate are tried as https and the others as http
+fn http_targets(subdomains: &[Subdomain], http_modules: &[Arc<dyn HttpModule>]) -> Vec<HttpTarget> {
+    let mut targets: Vec<HttpTarget> = Vec::new();
+    for (subdomain_index, subdomain) in subdomains.iter().enumerate() {
+        let mut seen_ports = HashSet::new();
+        for (port_index, port) in subdomain.open_ports.iter().enumerate() {
+            let scheme = match (&port.service, &port.certificate) {
+                (Some(service), _) if service.protocol == Protocol::Https => "https",
+                (Some(service), _) if service.protocol == Protocol::Http => "http",
+                (Some(_), _) => continue,
+                (None, Some(_)) => "https",
+                (None, None) => "http",
+            };
+            if !seen_ports.insert(port.port) {
+                continue;
+            }
+            for http_module in http_modules {
+                let target = format!("{}://{}:{}", scheme, &subdomain.domain, port.port);
+                targets.push((subdomain_index, port_index, http_module.clone(), target));
+            }
+        }
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_targets, resolve_subdomains, Event, PortStage, Scanner};
+    use crate::{
+        dns,
+        modules::{self, HttpModule, ModuleFilter, Port, Subdomain},
+        ports::IpVersion,
+        services::TlsCertificate,
+        targets,
+        testing::http_server,
+        throttle::Throttle,
+    };
+    use futures::StreamExt;
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+    use tokio::{net::TcpListener, sync::mpsc};
+
+    // the http modules a scan with this filter runs
+    fn allowed(module_filter: &ModuleFilter) -> Vec<Arc<dyn HttpModule>> {
+        modules::all_http_modules()
+            .into_iter()
+            .filter(|module| module_filter.allows(module.as_ref()))
+            .collect()
+    }
+
+    fn port_stage(ports: &[u16], ip_version: IpVersion) -> PortStage<'_> {
+        PortStage {
+            ports,
+            ip_version,
+            service_detection: false,
+            probe_timeout: Duration::from_millis(200),
+            concur: 10,
+            socks_proxy: None,
+            throttle: Arc::new(Throttle::unlimited()),
+            scanned_ips: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_pipeline_finds_local_listeners() {
+        let listener1 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener2 = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open1 = listener1.local_addr().unwrap().port();
+        let open2 = listener2.local_addr().unwrap().port();
+        // bind & drop so we have a port that is very likely closed
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let subdomains = vec![Subdomain::new(String::from("127.0.0.1"))];
+        let subdomains = resolve_subdomains(&dns::Resolver::default(), subdomains, 10).await;
+        assert_eq!(1, subdomains.len());
+
+        let (events, _receiver) = mpsc::unbounded_channel();
+        let subdomains = port_stage(&[open1, closed, open2], IpVersion::Any)
+            .scan(subdomains, &events)
+            .await;
+        let mut open_ports: Vec<u16> = subdomains[0].open_ports.iter().map(|p| p.port).collect();
+        open_ports.sort_unstable();
+        assert!(subdomains[0]
+            .open_ports
+            .iter()
+            .all(|port| port.ip.to_string() == "127.0.0.1"));
+        let mut expected = vec![open1, open2];
+        expected.sort_unstable();
+        assert_eq!(expected, open_ports);
+
+        let targets = http_targets(&subdomains, &allowed(&ModuleFilter::default()));
+        // the intrusive ones are off by default
+        let modules_count = modules::all_http_modules()
+            .iter()
+            .filter(|module| !module.tags().contains(&modules::INTRUSIVE))
+            .count();
+        assert_eq!(2 * modules_count, targets.len());
+        for port in [open1, open2] {
+            let url = format!("http://127.0.0.1:{}", port);
+            assert_eq!(
+                modules_count,
+                targets
+                    .iter()
+                    .filter(|(_, _, _, target)| *target == url)
+                    .count()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_every_address_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+
+        let mut www = Subdomain::new(String::from("www.kerkour.com"));
+        www.addresses = vec!["127.0.0.1".parse().unwrap(), "::1".parse().unwrap()];
+        let mut api = Subdomain::new(String::from("api.kerkour.com"));
+        api.addresses = vec!["127.0.0.1".parse().unwrap()];
+        let (events, _receiver) = mpsc::unbounded_channel();
+
+        let ports = [open];
+        let mut stage = port_stage(&ports, IpVersion::V4);
+        let subdomains = stage.scan(vec![www.clone(), api.clone()], &events).await;
+        assert_eq!(1, stage.scanned_ips.len());
+        for subdomain in &subdomains {
+            assert_eq!(1, subdomain.open_ports.len());
+            assert_eq!(open, subdomain.open_ports[0].port);
+            assert_eq!("127.0.0.1", subdomain.open_ports[0].ip.to_string());
+        }
+
+        let subdomains = port_stage(&ports, IpVersion::V6)
+            .scan(vec![www, api], &events)
+            .await;
+        assert!(subdomains
+            .iter()
+            .all(|subdomain| subdomain.open_ports.is_empty()));
+    }
+
+    fn tls_port(port: u16, sans: &[&str]) -> Port {
+        Port {
+            port,
+            ip: "127.0.0.1".parse().unwrap(),
+            service: None,
+            certificate: Some(TlsCertificate {
+                subject: String::from("CN=kerkour.com"),
+                issuer: String::from("CN=R3"),
+                sans: sans.iter().map(|san| san.to_string()).collect(),
+                not_before: None,
+                not_after: None,
+            }),
+            is_open: true,
+            findings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn https_target_when_tls() {
+        let mut www = Subdomain::new(String::from("www.kerkour.com"));
+        www.open_ports = vec![tls_port(8443, &["www.kerkour.com"])];
+
+        let targets = http_targets(&[www], &allowed(&ModuleFilter::default()));
+        assert!(!targets.is_empty());
+        assert!(targets
+            .iter()
+            .all(|(_, _, _, url)| url == "https://www.kerkour.com:8443"));
+    }
+
+    #[test]
+    fn http_targets_of_allowed_modules() {
+        let mut www = Subdomain::new(String::from("www.kerkour.com"));
+        www.open_ports = vec![tls_port(443, &["www.kerkour.com"])];
+        let module_filter = ModuleFilter::new(
+            &[String::from("http/git_*"), String::from("http/ds_store")],
+            &[String::from("http/git_config*")],
+            vec![String::from("disclosure")],
+            false,
+        )
+        .unwrap();
+
+        let targets = http_targets(&[www], &allowed(&module_filter));
+        let names: Vec<String> = targets
+            .iter()
+            .map(|(_, _, module, _)| module.name())
+            .collect();
+        assert_eq!(
+            vec![
+                "http/ds_store",
+                "http/git_directory_disclosute",
+                "http/git_head_disclosure",
+            ],
+            names
+        );
+    }
+
+    #[tokio::test]
+    async fn scanner_events() {
+        let addr = http_server(vec![(
+            "/.env",
+            200,
+            Vec::new(),
+            b"APP_KEY=secret\n".to_vec(),
+        )])
+        .await;
+        let scanner = Scanner::builder()
+            .targets(targets::parse_all(&[String::from("127.0.0.1")]).unwrap())
+            .ports(vec![addr.port()])
+            .modules(
+                ModuleFilter::new(&[String::from("http/dotenv")], &[], Vec::new(), false).unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let events: Vec<Event> = scanner.scan().collect().await;
+        assert!(matches!(events.first(), Some(Event::Started { .. })));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::PortOpen { domain, port } if domain == "127.0.0.1" && port.port == addr.port()
+        )));
+        let finding = events.iter().find_map(|event| match event {
+            Event::Finding { port, finding, .. } => Some((*port, finding)),
+            _ => None,
+        });
+        let (port, finding) = finding.unwrap();
+        assert_eq!(Some(addr.port()), port);
+        assert_eq!("http/dotenv", finding.module);
+        // the report has everything the events had
+        match events.last() {
+            Some(Event::Completed(report)) => {
+                assert_eq!(1, report.subdomains.len());
+                assert_eq!(1, report.subdomains[0].open_ports[0].findings.len());
+            }
+            event => panic!("last event: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn scanner_needs_targets() {
+        assert!(Scanner::builder().build().is_err());
+    }
+}
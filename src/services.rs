@@ -0,0 +1,609 @@
+use crate::modules::Port;
+use chrono::{DateTime, Utc};
+use futures::{stream, StreamExt};
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    fmt,
+    net::SocketAddr,
+    sync::{Arc, OnceLock},
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls::{
+        client::{ServerCertVerified, ServerCertVerifier},
+        Certificate, ClientConfig, ServerName,
+    },
+    TlsConnector,
+};
+use x509_parser::{certificate::X509Certificate, extensions::GeneralName, prelude::FromDer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Http,
+    Https,
+    // tls but not http inside
+    Tls,
+    Ssh,
+    Smtp,
+    Ftp,
+    Redis,
+    Mysql,
+    Unknown,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let protocol = match self {
+            Protocol::Http => "http",
+            Protocol::Https => "https",
+            Protocol::Tls => "tls",
+            Protocol::Ssh => "ssh",
+            Protocol::Smtp => "smtp",
+            Protocol::Ftp => "ftp",
+            Protocol::Redis => "redis",
+            Protocol::Mysql => "mysql",
+            Protocol::Unknown => "unknown",
+        };
+        write!(f, "{}", protocol)
+    }
+}
+
+// what runs behind an open port
+#[derive(Debug, Clone, Serialize)]
+pub struct Service {
+    pub protocol: Protocol,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    // the first line the server sent, if any
+    pub banner: Option<String>,
+}
+
+impl Service {
+    fn new(protocol: Protocol) -> Self {
+        Service {
+            protocol,
+            product: None,
+            version: None,
+            banner: None,
+        }
+    }
+}
+
+// what the tls certificate of a port says
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+// fills port.service for every port, host is used for the Host header and SNI
+pub async fn detect_services(
+    concur: usize,
+    host: &str,
+    timeout: Duration,
+    ports: Vec<Port>,
+) -> Vec<Port> {
+    stream::iter(ports)
+        .map(|mut port| async move {
+            port.service = Some(detect(SocketAddr::new(port.ip, port.port), host, timeout).await);
+            port
+        })
+        .buffer_unordered(concur)
+        .collect()
+        .await
+}
+
+// fills port.certificate for every port that may speak tls
+// ports we know are something else are skipped
+pub async fn fetch_certificates(
+    concur: usize,
+    host: &str,
+    timeout: Duration,
+    ports: Vec<Port>,
+) -> Vec<Port> {
+    stream::iter(ports)
+        .map(|mut port| async move {
+            let may_be_tls = match &port.service {
+                Some(service) => matches!(service.protocol, Protocol::Https | Protocol::Tls),
+                None => true,
+            };
+            if may_be_tls {
+                port.certificate =
+                    tls_certificate(SocketAddr::new(port.ip, port.port), host, timeout).await;
+            }
+            port
+        })
+        .buffer_unordered(concur)
+        .collect()
+        .await
+}
+
+// does a handshake and reads the certificate the server sent
+pub async fn tls_certificate(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Option<TlsCertificate> {
+    let stream = tls_connect(addr, host, timeout).await?;
+    let (_, connection) = stream.get_ref();
+    let certificate = connection.peer_certificates()?.first()?;
+    parse_certificate(&certificate.0)
+}
+
+fn parse_certificate(der: &[u8]) -> Option<TlsCertificate> {
+    let (_, certificate) = X509Certificate::from_der(der).ok()?;
+    let sans = match certificate.subject_alternative_name() {
+        Ok(Some(sans)) => sans
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(name) => Some(name.to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let validity = certificate.validity();
+    Some(TlsCertificate {
+        subject: certificate.subject().to_string(),
+        issuer: certificate.issuer().to_string(),
+        sans,
+        not_before: DateTime::from_timestamp(validity.not_before.timestamp(), 0),
+        not_after: DateTime::from_timestamp(validity.not_after.timestamp(), 0),
+    })
+}
+
+// waits for a banner first, then tries the light probes one after the other
+// every probe gets its own connection
+pub async fn detect(addr: SocketAddr, host: &str, timeout: Duration) -> Service {
+    if let Some(service) = detect_from_banner(addr, timeout).await {
+        return service;
+    }
+
+    let head = format!(
+        "HEAD / HTTP/1.0\r\nHost: {}\r\nUser-Agent: tricoder\r\n\r\n",
+        host
+    );
+    if let Some(response) = exchange(connect(addr, timeout).await, head.as_bytes(), timeout).await {
+        if let Some(service) = parse_http(&response, Protocol::Http) {
+            return service;
+        }
+        // redis closes on http but tells us why first
+        if response.starts_with(b"-") {
+            return Service::new(Protocol::Redis);
+        }
+    }
+
+    if let Some(tls_stream) = tls_connect(addr, host, timeout).await {
+        let response = exchange(Some(tls_stream), head.as_bytes(), timeout).await;
+        return response
+            .and_then(|response| parse_http(&response, Protocol::Https))
+            .unwrap_or_else(|| Service::new(Protocol::Tls));
+    }
+
+    if let Some(service) = detect_redis(addr, timeout).await {
+        return service;
+    }
+
+    Service::new(Protocol::Unknown)
+}
+
+// ssh, smtp, ftp and mysql talk first
+async fn detect_from_banner(addr: SocketAddr, timeout: Duration) -> Option<Service> {
+    let mut stream = connect(addr, timeout).await?;
+    let banner = read(&mut stream, timeout).await?;
+
+    // mysql handshake: 3 bytes length, sequence 0, protocol 10, server version\0
+    if banner.len() > 5 && banner[3] == 0 && banner[4] == 0x0a {
+        let version: Vec<u8> = banner[5..]
+            .iter()
+            .take_while(|b| **b != 0)
+            .cloned()
+            .collect();
+        let version = String::from_utf8_lossy(&version).to_string();
+        let mut service = Service::new(Protocol::Mysql);
+        service.product = Some(if version.contains("MariaDB") {
+            String::from("MariaDB")
+        } else {
+            String::from("MySQL")
+        });
+        // mariadb prefixes its version with 5.5.5-
+        service.version = version
+            .trim_start_matches("5.5.5-")
+            .split('-')
+            .next()
+            .map(|version| version.to_string());
+        return Some(service);
+    }
+
+    let text = String::from_utf8_lossy(&banner);
+    let first_line = text.lines().next().unwrap_or_default().trim().to_string();
+
+    let mut service = if let Some(software) = first_line.strip_prefix("SSH-") {
+        // SSH-2.0-OpenSSH_8.9p1 Ubuntu-3
+        let mut service = Service::new(Protocol::Ssh);
+        let software = software.split_once('-').map(|(_, software)| software);
+        if let Some(software) = software.and_then(|software| software.split(' ').next()) {
+            match software.split_once('_') {
+                Some((product, version)) => {
+                    service.product = Some(product.to_string());
+                    service.version = Some(version.to_string());
+                }
+                None => service.product = Some(software.to_string()),
+            }
+        }
+        service
+    } else if first_line.starts_with("220") {
+        let lower = first_line.to_lowercase();
+        let protocol = if lower.contains("ftp") {
+            Protocol::Ftp
+        } else if lower.contains("smtp") {
+            Protocol::Smtp
+        } else {
+            // both greet with 220, only smtp knows EHLO
+            match exchange(Some(stream), b"EHLO tricoder\r\n", timeout).await {
+                Some(response) if response.starts_with(b"250") => Protocol::Smtp,
+                _ => Protocol::Ftp,
+            }
+        };
+        let mut service = Service::new(protocol);
+        if let Some((product, version)) = product_version(&first_line) {
+            service.product = Some(product);
+            service.version = Some(version);
+        }
+        service
+    } else {
+        Service::new(Protocol::Unknown)
+    };
+    service.banner = Some(first_line);
+    Some(service)
+}
+
+async fn detect_redis(addr: SocketAddr, timeout: Duration) -> Option<Service> {
+    let mut stream = connect(addr, timeout).await?;
+    stream.write_all(b"PING\r\n").await.ok()?;
+    let response = read(&mut stream, timeout).await?;
+    if response.starts_with(b"+PONG") {
+        let mut service = Service::new(Protocol::Redis);
+        service.product = Some(String::from("Redis"));
+        if let Some(info) = exchange(Some(stream), b"INFO server\r\n", timeout).await {
+            service.version = String::from_utf8_lossy(&info)
+                .lines()
+                .find_map(|line| line.strip_prefix("redis_version:"))
+                .map(|version| version.trim().to_string());
+        }
+        return Some(service);
+    }
+    // auth required, still redis
+    if response.starts_with(b"-NOAUTH") || response.starts_with(b"-ERR") {
+        let mut service = Service::new(Protocol::Redis);
+        service.product = Some(String::from("Redis"));
+        return Some(service);
+    }
+    None
+}
+
+// HTTP/1.1 200 OK ... Server: nginx/1.18.0 (Ubuntu)
+fn parse_http(response: &[u8], protocol: Protocol) -> Option<Service> {
+    if !response.starts_with(b"HTTP/") {
+        return None;
+    }
+    let text = String::from_utf8_lossy(response);
+    let mut service = Service::new(protocol);
+    service.banner = text.lines().next().map(|line| line.trim().to_string());
+    let server = text.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case("server") {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    });
+    if let Some(server) = server {
+        let software = server.split(' ').next().unwrap_or_default();
+        match software.split_once('/') {
+            Some((product, version)) => {
+                service.product = Some(product.to_string());
+                service.version = Some(version.to_string());
+            }
+            None => service.product = Some(software.to_string()),
+        }
+    }
+    Some(service)
+}
+
+// "220 (vsFTPd 3.0.3)" -> vsFTPd 3.0.3
+fn product_version(banner: &str) -> Option<(String, String)> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(r"([A-Za-z][\w-]*)[ /_]v?(\d+\.\d+(?:\.\d+)*\w*)")
+            .expect("services: compiling product regexp")
+    });
+    let captures = regex.captures(banner)?;
+    Some((captures[1].to_string(), captures[2].to_string()))
+}
+
+async fn connect(addr: SocketAddr, timeout: Duration) -> Option<TcpStream> {
+    match tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => Some(stream),
+        _ => None,
+    }
+}
+
+async fn read<S: AsyncRead + Unpin>(stream: &mut S, timeout: Duration) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; 4096];
+    match tokio::time::timeout(timeout, stream.read(&mut buffer)).await {
+        Ok(Ok(n)) if n > 0 => {
+            buffer.truncate(n);
+            Some(buffer)
+        }
+        _ => None,
+    }
+}
+
+// sends the probe and reads the first answer
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: Option<S>,
+    probe: &[u8],
+    timeout: Duration,
+) -> Option<Vec<u8>> {
+    let mut stream = stream?;
+    tokio::time::timeout(timeout, stream.write_all(probe))
+        .await
+        .ok()?
+        .ok()?;
+    read(&mut stream, timeout).await
+}
+
+async fn tls_connect(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Option<tokio_rustls::client::TlsStream<TcpStream>> {
+    let stream = connect(addr, timeout).await?;
+    // ips can't be a server name, sni is not sent for them anyway
+    let server_name = ServerName::try_from(host)
+        .or_else(|_| ServerName::try_from("localhost"))
+        .ok()?;
+    match tokio::time::timeout(timeout, tls_connector().connect(server_name, stream)).await {
+        Ok(Ok(stream)) => Some(stream),
+        _ => None,
+    }
+}
+
+// we want to see every certificate, even the broken ones
+struct NoCertificateVerification;
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+fn tls_connector() -> TlsConnector {
+    static CONNECTOR: OnceLock<TlsConnector> = OnceLock::new();
+    CONNECTOR
+        .get_or_init(|| {
+            let config = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_custom_certificate_verifier(Arc::new(NoCertificateVerification))
+                .with_no_client_auth();
+            TlsConnector::from(Arc::new(config))
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect, tls_certificate, Protocol};
+    use crate::testing::{mock_server, reply_server};
+    use std::{sync::Arc, time::Duration};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{
+        rustls::{Certificate, PrivateKey, ServerConfig},
+        TlsAcceptor,
+    };
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    #[tokio::test]
+    async fn detect_ssh() {
+        let addr = mock_server(|mut stream| async move {
+            let _ = stream
+                .write_all(b"SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.1\r\n")
+                .await;
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Ssh, service.protocol);
+        assert_eq!(Some(String::from("OpenSSH")), service.product);
+        assert_eq!(Some(String::from("8.9p1")), service.version);
+    }
+
+    #[tokio::test]
+    async fn detect_smtp() {
+        let addr = mock_server(|mut stream| async move {
+            let _ = stream.write_all(b"220 mx.kerkour.com Exim 4.96\r\n").await;
+            let mut buffer = [0; 1024];
+            if let Ok(n) = stream.read(&mut buffer).await {
+                if buffer[..n].starts_with(b"EHLO") {
+                    let _ = stream.write_all(b"250-mx.kerkour.com Hello\r\n").await;
+                }
+            }
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Smtp, service.protocol);
+        assert_eq!(Some(String::from("Exim")), service.product);
+        assert_eq!(Some(String::from("4.96")), service.version);
+    }
+
+    #[tokio::test]
+    async fn detect_ftp() {
+        let addr = mock_server(|mut stream| async move {
+            let _ = stream.write_all(b"220 (vsFTPd 3.0.3)\r\n").await;
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Ftp, service.protocol);
+        assert_eq!(Some(String::from("vsFTPd")), service.product);
+        assert_eq!(Some(String::from("3.0.3")), service.version);
+    }
+
+    #[tokio::test]
+    async fn detect_mysql() {
+        let addr = mock_server(|mut stream| async move {
+            let mut packet = vec![0x4a, 0x00, 0x00, 0x00, 0x0a];
+            packet.extend_from_slice(b"5.5.5-10.6.12-MariaDB\0");
+            packet.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]);
+            let _ = stream.write_all(&packet).await;
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Mysql, service.protocol);
+        assert_eq!(Some(String::from("MariaDB")), service.product);
+        assert_eq!(Some(String::from("10.6.12")), service.version);
+    }
+
+    #[tokio::test]
+    async fn detect_redis() {
+        let addr = mock_server(|mut stream| async move {
+            let mut buffer = [0; 1024];
+            while let Ok(n) = stream.read(&mut buffer).await {
+                let response: &[u8] = if buffer[..n].starts_with(b"PING") {
+                    b"+PONG\r\n"
+                } else if buffer[..n].starts_with(b"INFO") {
+                    b"$40\r\n# Server\r\nredis_version:7.0.11\r\n\r\n"
+                } else {
+                    break;
+                };
+                if stream.write_all(response).await.is_err() {
+                    break;
+                }
+            }
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Redis, service.protocol);
+        assert_eq!(Some(String::from("7.0.11")), service.version);
+    }
+
+    #[tokio::test]
+    async fn detect_http() {
+        let addr = reply_server(
+            b"HTTP/1.1 200 OK\r\nServer: nginx/1.18.0 (Ubuntu)\r\nContent-Length: 0\r\n\r\n",
+        )
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Http, service.protocol);
+        assert_eq!(Some(String::from("nginx")), service.product);
+        assert_eq!(Some(String::from("1.18.0")), service.version);
+    }
+
+    fn tls_acceptor(sans: &[&str]) -> TlsAcceptor {
+        let sans: Vec<String> = sans.iter().map(|san| san.to_string()).collect();
+        let cert = rcgen::generate_simple_self_signed(sans).unwrap();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![Certificate(cert.serialize_der().unwrap())],
+                PrivateKey(cert.serialize_private_key_der()),
+            )
+            .unwrap();
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    #[tokio::test]
+    async fn detect_https_and_tls() {
+        let acceptor = tls_acceptor(&["localhost"]);
+
+        let https_acceptor = acceptor.clone();
+        let https = mock_server(move |stream| {
+            let acceptor = https_acceptor.clone();
+            async move {
+                if let Ok(mut stream) = acceptor.accept(stream).await {
+                    let mut buffer = [0; 1024];
+                    let _ = stream.read(&mut buffer).await;
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 404 Not Found\r\nServer: Caddy\r\n\r\n")
+                        .await;
+                }
+            }
+        })
+        .await;
+        let service = detect(https, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Https, service.protocol);
+        assert_eq!(Some(String::from("Caddy")), service.product);
+
+        // tls wrapped service that doesnt speak http
+        let tls = mock_server(move |stream| {
+            let acceptor = acceptor.clone();
+            async move {
+                if let Ok(mut stream) = acceptor.accept(stream).await {
+                    let mut buffer = [0; 1024];
+                    let _ = stream.read(&mut buffer).await;
+                }
+            }
+        })
+        .await;
+        let service = detect(tls, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Tls, service.protocol);
+    }
+
+    #[tokio::test]
+    async fn detect_unknown() {
+        // accepts and never talks
+        let addr = mock_server(|mut stream| async move {
+            let mut buffer = [0; 1024];
+            while let Ok(n) = stream.read(&mut buffer).await {
+                if n == 0 {
+                    break;
+                }
+            }
+        })
+        .await;
+        let service = detect(addr, "localhost", TIMEOUT).await;
+        assert_eq!(Protocol::Unknown, service.protocol);
+    }
+
+    #[tokio::test]
+    async fn read_tls_certificate() {
+        let acceptor = tls_acceptor(&["localhost", "www.kerkour.com"]);
+        let addr = mock_server(move |stream| {
+            let acceptor = acceptor.clone();
+            async move {
+                let _ = acceptor.accept(stream).await;
+            }
+        })
+        .await;
+
+        let certificate = tls_certificate(addr, "localhost", TIMEOUT).await.unwrap();
+        assert_eq!(vec!["localhost", "www.kerkour.com"], certificate.sans);
+        assert_eq!("CN=rcgen self signed cert", certificate.subject);
+        assert_eq!(certificate.subject, certificate.issuer);
+        assert!(certificate.not_after > certificate.not_before);
+
+        // plain tcp has no certificate
+        let plain = mock_server(|_| async {}).await;
+        assert!(tls_certificate(plain, "localhost", TIMEOUT).await.is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    http_client::HttpClient,
+    modules::{excerpt, Finding, Module, Severity, Subdomain},
+    Error,
+};
+use reqwest::header;
+use serde::Deserialize;
+use std::fs;
+
+// shipped with the binary, --takeover-fingerprints replaces it
+const FINGERPRINTS: &str = include_str!("../data/takeover_fingerprints.json");
+
+// a service that hands out names under its own domain
+// a cname to it that nobody claimed anymore can be claimed by anyone
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fingerprint {
+    pub service: String,
+    // suffixes of the cname targets that belong to the service
+    pub cnames: Vec<String>,
+    // what the service answers for a name that is not claimed
+    #[serde(default)]
+    pub body: Option<String>,
+    // the target not resolving is enough to claim it
+    #[serde(default)]
+    pub nxdomain: bool,
+}
+
+impl Fingerprint {
+    fn matches(&self, cname: &str) -> bool {
+        let cname = cname.trim_end_matches('.').to_lowercase();
+        self.cnames
+            .iter()
+            .any(|suffix| cname.ends_with(&suffix.to_lowercase()))
+    }
+}
+
+pub fn default_fingerprints() -> Vec<Fingerprint> {
+    serde_json::from_str(FINGERPRINTS).expect("takeover: parsing the default fingerprints")
+}
+
+pub fn read_fingerprints(path: &str) -> Result<Vec<Fingerprint>, Error> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Fingerprints(format!("{}: {}", path, err)))?;
+    serde_json::from_str(&content).map_err(|err| Error::Fingerprints(format!("{}: {}", path, err)))
+}
+
+// Takeover looks at where the cnames of a subdomain point
+pub struct Takeover {
+    fingerprints: Vec<Fingerprint>,
+    // where the http check connects, only changes in the tests
+    http_port: u16,
+}
+
+impl Takeover {
+    pub fn new(fingerprints: Vec<Fingerprint>) -> Self {
+        Takeover {
+            fingerprints,
+            http_port: 80,
+        }
+    }
+
+    pub async fn check(&self, http_client: &HttpClient, subdomain: &Subdomain) -> Option<Finding> {
+        let (cname, fingerprint) = subdomain.cnames.iter().find_map(|cname| {
+            self.fingerprints
+                .iter()
+                .find(|fingerprint| fingerprint.matches(cname))
+                .map(|fingerprint| (cname, fingerprint))
+        })?;
+        let chain: Vec<String> = subdomain
+            .records
+            .iter()
+            .map(|record| record.to_string())
+            .collect();
+        let remediation = format!(
+            "remove the CNAME record or claim {} on {}",
+            cname, fingerprint.service
+        );
+
+        // the chain ends nowhere
+        let address = match subdomain.addresses.first() {
+            Some(address) => *address,
+            None if fingerprint.nxdomain => {
+                return Some(
+                    self.finding(subdomain)
+                        .with_evidence(
+                            format!("A {}", subdomain.domain),
+                            format!("{}\n{} NXDOMAIN", chain.join("\n"), cname),
+                        )
+                        .with_remediation(&remediation),
+                )
+            }
+            None => return None,
+        };
+
+        // the service is there but says the name is not claimed
+        let signature = fingerprint.body.as_ref()?;
+        // the address we resolved, not whatever the system resolver thinks
+        let url = format!("http://{}:{}/", address, self.http_port);
+        let res = http_client
+            .get(&url)
+            .header(header::HOST, subdomain.domain.as_str())
+            .send()
+            .await
+            .ok()?;
+        let body = res.text().await.ok()?;
+        if !body.contains(signature.as_str()) {
+            return None;
+        }
+        Some(
+            self.finding(subdomain)
+                .with_evidence(
+                    format!("{}\nGET / Host: {}", chain.join("\n"), subdomain.domain),
+                    excerpt(&body, 500),
+                )
+                .with_remediation(&remediation),
+        )
+    }
+
+    fn finding(&self, subdomain: &Subdomain) -> Finding {
+        Finding::new(
+            self.name(),
+            Severity::High,
+            format!("dns://{}", subdomain.domain),
+        )
+    }
+}
+
+impl Module for Takeover {
+    fn name(&self) -> String {
+        String::from("dns/takeover")
+    }
+    fn description(&self) -> String {
+        String::from("find cnames to cloud services that are not claimed anymore")
+    }
+    fn tags(&self) -> Vec<&str> {
+        vec!["dns"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::High)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from(
+            "GET http://<address>/ Host: {subdomain} when the cname points to a known service",
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{default_fingerprints, Fingerprint, Takeover};
+    use crate::{
+        dns,
+        http_client::HttpClient,
+        modules::{Severity, Subdomain},
+        testing::{dns_server, http_server, local_resolver},
+    };
+    use reqwest::Client;
+
+    #[test]
+    fn default_fingerprints_parse() {
+        let fingerprints = default_fingerprints();
+        let s3 = fingerprints
+            .iter()
+            .find(|fingerprint| fingerprint.service == "aws s3")
+            .unwrap();
+        assert!(s3.matches("assets.s3.amazonaws.com."));
+        assert!(!s3.matches("s3.amazonaws.com.kerkour.com"));
+        assert_eq!(Some("NoSuchBucket"), s3.body.as_deref());
+    }
+
+    #[tokio::test]
+    async fn takeover_candidates() {
+        let http = http_server(vec![(
+            "/",
+            404,
+            Vec::new(),
+            b"<Error><Code>NoSuchBucket</Code></Error>".to_vec(),
+        )])
+        .await;
+        let dns_resolver = local_resolver(
+            dns_server(vec![
+                ("assets.kerkour.com", "kerkour-assets.s3.amazonaws.com"),
+                ("kerkour-assets.s3.amazonaws.com", "127.0.0.1"),
+                ("app.kerkour.com", "kerkour.azurewebsites.net"),
+                ("blog.kerkour.com", "kerkour.github.io"),
+                ("kerkour.github.io", "127.0.0.1"),
+                ("old.kerkour.com", "gone.example.net"),
+            ])
+            .await,
+        );
+        let mut fingerprints = default_fingerprints();
+        // a service where a dangling cname can not be claimed
+        fingerprints.push(Fingerprint {
+            service: String::from("local"),
+            cnames: vec![String::from(".example.net")],
+            body: None,
+            nxdomain: false,
+        });
+        let mut takeover = Takeover::new(fingerprints);
+        takeover.http_port = http.port();
+        let client = HttpClient::from(Client::new());
+        let check = |domain: &'static str| {
+            let dns_resolver = dns_resolver.clone();
+            let takeover = &takeover;
+            let client = &client;
+            async move {
+                let subdomain = dns::resolves(&dns_resolver, Subdomain::new(domain.to_string()))
+                    .await
+                    .unwrap();
+                takeover.check(client, &subdomain).await
+            }
+        };
+
+        // the bucket is gone
+        let finding = check("assets.kerkour.com").await.unwrap();
+        assert_eq!(Severity::High, finding.severity);
+        assert!(finding.evidence.unwrap().response.contains("NoSuchBucket"));
+        // the azure app is gone
+        let finding = check("app.kerkour.com").await.unwrap();
+        assert!(finding.evidence.unwrap().response.contains("NXDOMAIN"));
+        assert!(check("blog.kerkour.com").await.is_none());
+        // nxdomain is not enough for every service
+        assert!(check("old.kerkour.com").await.is_none());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::Error;
+use ipnet::IpNet;
+use std::{collections::BTreeSet, fmt, fs, net::IpAddr};
+use url::{Host, Url};
+
+// a /16 or a /112, anything bigger is probably a typo
+const MAX_CIDR_HOSTS: u32 = 16;
+
+// what the user asked us to scan
+// domains go through the subdomain modules, ips go straight to the port scan
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Target {
+    Domain(String),
+    Ip(IpAddr),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Domain(domain) => write!(f, "{}", domain),
+            Target::Ip(ip) => write!(f, "{}", ip),
+        }
+    }
+}
+
+impl Target {
+    // is the host the domain itself or one of its subdomains
+    pub fn in_scope(&self, host: &str) -> bool {
+        match self {
+            Target::Domain(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            Target::Ip(ip) => host == ip.to_string(),
+        }
+    }
+}
+
+// a domain, an ip, a cidr range or an url
+pub fn parse(input: &str) -> Result<Vec<Target>, Error> {
+    let input = input.trim();
+
+    if input.contains("://") {
+        let url = Url::parse(input).map_err(|_| Error::InvalidTarget(input.to_string()))?;
+        return match url.host() {
+            Some(Host::Domain(domain)) => Ok(vec![parse_domain(domain)?]),
+            Some(Host::Ipv4(ip)) => Ok(vec![Target::Ip(ip.into())]),
+            Some(Host::Ipv6(ip)) => Ok(vec![Target::Ip(ip.into())]),
+            None => Err(Error::InvalidTarget(input.to_string())),
+        };
+    }
+
+    if let Ok(ip) = input.parse::<IpAddr>() {
+        return Ok(vec![Target::Ip(ip)]);
+    }
+
+    if input.contains('/') {
+        let net: IpNet = input
+            .parse()
+            .map_err(|_| Error::InvalidTarget(input.to_string()))?;
+        let host_bits = u32::from(net.max_prefix_len() - net.prefix_len());
+        if host_bits > MAX_CIDR_HOSTS {
+            return Err(Error::InvalidTarget(format!(
+                "{}: range is bigger than /{}",
+                input,
+                net.max_prefix_len() as u32 - MAX_CIDR_HOSTS
+            )));
+        }
+        return Ok(net.hosts().map(Target::Ip).collect());
+    }
+
+    Ok(vec![parse_domain(input)?])
+}
+
+fn parse_domain(input: &str) -> Result<Target, Error> {
+    let domain = input.trim_end_matches('.').to_lowercase();
+    let is_valid = !domain.is_empty()
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+    if !is_valid {
+        return Err(Error::InvalidTarget(input.to_string()));
+    }
+    Ok(Target::Domain(domain))
+}
+
+// parses every input and drops the duplicates
+pub fn parse_all<S: AsRef<str>>(inputs: &[S]) -> Result<Vec<Target>, Error> {
+    let mut targets = BTreeSet::new();
+    for input in inputs {
+        targets.extend(parse(input.as_ref())?);
+    }
+    Ok(targets.into_iter().collect())
+}
+
+// one target per line, empty lines and # comments are skipped
+pub fn read_file(path: &str) -> Result<Vec<String>, Error> {
+    let content =
+        fs::read_to_string(path).map_err(|err| Error::TargetsFile(format!("{}: {}", path, err)))?;
+    Ok(lines(&content))
+}
+
+pub fn lines(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lines, parse, parse_all, Target};
+
+    #[test]
+    fn parse_targets() {
+        let ip = |ip: &str| Target::Ip(ip.parse().unwrap());
+        let domain = |domain: &str| Target::Domain(domain.to_string());
+
+        assert_eq!(vec![domain("kerkour.com")], parse("Kerkour.com.").unwrap());
+        assert_eq!(vec![ip("10.0.0.1")], parse("10.0.0.1").unwrap());
+        assert_eq!(vec![ip("::1")], parse("::1").unwrap());
+        assert_eq!(
+            vec![domain("www.kerkour.com")],
+            parse("https://www.kerkour.com:8443/login").unwrap()
+        );
+        assert_eq!(vec![ip("::1")], parse("http://[::1]:8080/").unwrap());
+        assert_eq!(
+            vec![ip("10.0.0.1"), ip("10.0.0.2")],
+            parse("10.0.0.0/30").unwrap()
+        );
+        assert_eq!(254, parse("10.0.0.0/24").unwrap().len());
+
+        assert!(parse("10.0.0.0/8").is_err());
+        assert!(parse("10.0.0.0/33").is_err());
+        assert!(parse("kerkour .com").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn parse_all_dedups() {
+        let targets = parse_all(&[
+            "kerkour.com",
+            "https://kerkour.com/",
+            "10.0.0.1",
+            "10.0.0.0/30",
+        ])
+        .unwrap();
+        assert_eq!(3, targets.len());
+    }
+
+    #[test]
+    fn in_scope() {
+        let target = Target::Domain(String::from("kerkour.com"));
+
+        assert!(target.in_scope("kerkour.com"));
+        assert!(target.in_scope("www.kerkour.com"));
+        assert!(!target.in_scope("notkerkour.com"));
+        assert!(!target.in_scope("kerkour.com.evil.com"));
+    }
+
+    #[test]
+    fn targets_file_lines() {
+        let content = "kerkour.com\n\n# staging\n10.0.0.0/30 # lab\n  ";
+        assert_eq!(vec!["kerkour.com", "10.0.0.0/30"], lines(content));
+    }
+}
@@ -0,0 +1,326 @@
+// helpers for the tests that need a server to talk to
+use crate::dns::{self, Resolver};
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
+use trust_dns_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig},
+    proto::{
+        op::{Message, MessageType, OpCode, ResponseCode},
+        rr::{
+            rdata::{MX, SOA, SRV, TXT},
+            Name, RData, Record, RecordType,
+        },
+    },
+};
+
+// accepts connections forever and hands them to handler
+pub async fn mock_server<F, Fut>(handler: F) -> SocketAddr
+where
+    F: Fn(TcpStream) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handler(stream));
+        }
+    });
+    addr
+}
+
+// answers every request with the same bytes
+pub async fn reply_server(response: &'static [u8]) -> SocketAddr {
+    mock_server(move |mut stream| async move {
+        let mut buffer = [0; 1024];
+        while let Ok(n) = stream.read(&mut buffer).await {
+            if n == 0 || stream.write_all(response).await.is_err() {
+                break;
+            }
+        }
+    })
+    .await
+}
+
+// tiny http/1.0 server, routes is a list of (path, status, headers, body)
+// anything else is a 404
+pub async fn http_server(routes: Vec<(&'static str, u16, Vec<String>, Vec<u8>)>) -> SocketAddr {
+    let routes = std::sync::Arc::new(routes);
+    mock_server(move |mut stream| {
+        let routes = routes.clone();
+        async move {
+            let path = match request_path(&mut stream).await {
+                Some(path) => path,
+                None => return,
+            };
+            let (status, headers, body) = routes
+                .iter()
+                .find(|(route, ..)| *route == path)
+                .map(|(_, status, headers, body)| (*status, headers.clone(), body.clone()))
+                .unwrap_or((404, Vec::new(), b"not found".to_vec()));
+            respond(&mut stream, status, headers, body).await;
+        }
+    })
+    .await
+}
+
+// serves the files under root at prefix/..., without directory listing
+// anything else is a 404
+pub async fn file_server(root: PathBuf, prefix: &'static str) -> SocketAddr {
+    let root = std::sync::Arc::new(root);
+    mock_server(move |mut stream| {
+        let root = root.clone();
+        async move {
+            let path = match request_path(&mut stream).await {
+                Some(path) => path,
+                None => return,
+            };
+            let file = path
+                .strip_prefix(prefix)
+                .filter(|file| !file.split('/').any(|part| part == ".."))
+                .map(|file| root.join(file.trim_start_matches('/')))
+                .filter(|file| file.is_file());
+            match file.and_then(|file| std::fs::read(file).ok()) {
+                Some(body) => respond(&mut stream, 200, Vec::new(), body).await,
+                None => respond(&mut stream, 404, Vec::new(), b"not found".to_vec()).await,
+            }
+        }
+    })
+    .await
+}
+
+// the path of the request, without the query
+async fn request_path(stream: &mut TcpStream) -> Option<String> {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let path = request.split(' ').nth(1).unwrap_or("/");
+    Some(path.split('?').next().unwrap_or(path).to_string())
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, headers: Vec<String>, body: Vec<u8>) {
+    let mut response = format!(
+        "HTTP/1.0 {} tricoder\r\nContent-Length: {}\r\n",
+        status,
+        body.len()
+    );
+    for header in headers {
+        response.push_str(&format!("{}\r\n", header));
+    }
+    response.push_str("\r\n");
+    let mut response = response.into_bytes();
+    response.extend_from_slice(&body);
+    let _ = stream.write_all(&response).await;
+}
+
+// dns server answering queries from records, a list of (name, value)
+// value is written like in a zone file: an ip (A/AAAA), "NS name", "MX 10 name",
+// "TXT text", "SRV 0 0 port name", "SOA mname rname" or a bare name (CNAME)
+// names can start with *. for wildcards, anything else is NXDOMAIN
+pub async fn dns_server(records: Vec<(&'static str, &'static str)>) -> SocketAddr {
+    zone_server(records, false).await
+}
+
+// same but the zone can be transfered (AXFR/IXFR over tcp) when allow_transfer
+pub async fn zone_server(
+    records: Vec<(&'static str, &'static str)>,
+    allow_transfer: bool,
+) -> SocketAddr {
+    let records = std::sync::Arc::new(records);
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    let listener = TcpListener::bind(addr).await.unwrap();
+
+    let udp_records = records.clone();
+    tokio::spawn(async move {
+        let mut buffer = [0; 512];
+        while let Ok((n, peer)) = socket.recv_from(&mut buffer).await {
+            let request = match Message::from_vec(&buffer[..n]) {
+                Ok(request) => request,
+                Err(_) => continue,
+            };
+            let response = dns_answer(&udp_records, &request);
+            let _ = socket.send_to(&response.to_vec().unwrap(), peer).await;
+        }
+    });
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let records = records.clone();
+            tokio::spawn(async move {
+                // every message is prefixed by its length on tcp
+                while let Ok(len) = stream.read_u16().await {
+                    let mut buffer = vec![0; len as usize];
+                    if stream.read_exact(&mut buffer).await.is_err() {
+                        return;
+                    }
+                    let request = match Message::from_vec(&buffer) {
+                        Ok(request) => request,
+                        Err(_) => return,
+                    };
+                    let response = match request.queries().first().map(|query| query.query_type()) {
+                        Some(RecordType::AXFR) | Some(RecordType::IXFR) => {
+                            zone_transfer(&records, &request, allow_transfer)
+                        }
+                        _ => dns_answer(&records, &request),
+                    };
+                    let response = response.to_vec().unwrap();
+                    let _ = stream.write_u16(response.len() as u16).await;
+                    let _ = stream.write_all(&response).await;
+                }
+            });
+        }
+    });
+    addr
+}
+
+// a resolver that only asks the server at addr
+pub fn local_resolver(addr: SocketAddr) -> Resolver {
+    let nameservers = NameServerConfigGroup::from_ips_clear(&[addr.ip()], addr.port(), true);
+    dns::new_resolver(
+        vec![ResolverConfig::from_parts(None, Vec::new(), nameservers)],
+        None,
+    )
+}
+
+fn dns_response(request: &Message) -> Message {
+    let mut response = Message::new();
+    response
+        .set_id(request.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(OpCode::Query)
+        .set_authoritative(true)
+        .set_recursion_desired(request.recursion_desired())
+        .set_recursion_available(true);
+    if let Some(query) = request.queries().first() {
+        response.add_query(query.clone());
+    }
+    response
+}
+
+fn dns_answer(records: &[(&'static str, &'static str)], request: &Message) -> Message {
+    let mut response = dns_response(request);
+    let query = match request.queries().first() {
+        Some(query) => query.clone(),
+        None => return response,
+    };
+
+    let mut name = query.name().to_utf8().trim_end_matches('.').to_lowercase();
+    // follows cnames until an answer or nothing
+    // like real servers a chain that ends nowhere is NXDOMAIN with the cnames in it
+    for _ in 0..8 {
+        let values = records_of(records, &name);
+        if values.is_empty() {
+            response.set_response_code(ResponseCode::NXDomain);
+            break;
+        }
+        let mut target = None;
+        for value in values {
+            let record = stub_record(&name, value);
+            if let Some(RData::CNAME(cname)) = record.data() {
+                target = Some(cname.to_utf8().trim_end_matches('.').to_string());
+            } else if record.record_type() != query.query_type() {
+                continue;
+            }
+            response.add_answer(record);
+        }
+        match target {
+            Some(target) if query.query_type() != RecordType::CNAME => name = target,
+            _ => break,
+        }
+    }
+    response
+}
+
+// the soa, every other record of the zone, the soa again
+fn zone_transfer(
+    records: &[(&'static str, &'static str)],
+    request: &Message,
+    allow_transfer: bool,
+) -> Message {
+    let mut response = dns_response(request);
+    if !allow_transfer {
+        response.set_response_code(ResponseCode::Refused);
+        return response;
+    }
+    let records: Vec<Record> = records
+        .iter()
+        .map(|(name, value)| stub_record(name, value))
+        .collect();
+    let (soa, others): (Vec<Record>, Vec<Record>) = records
+        .into_iter()
+        .partition(|record| record.record_type() == RecordType::SOA);
+    let soa = match soa.into_iter().next() {
+        Some(soa) => soa,
+        None => {
+            response.set_response_code(ResponseCode::NotAuth);
+            return response;
+        }
+    };
+    response.add_answer(soa.clone());
+    response.add_answers(others);
+    response.add_answer(soa);
+    response
+}
+
+fn stub_record(name: &str, value: &str) -> Record {
+    let dns_name = |name: &str| Name::from_utf8(format!("{}.", name)).unwrap();
+    let fields: Vec<&str> = value.split(' ').collect();
+    let rdata = match (fields[0], value.parse::<IpAddr>()) {
+        (_, Ok(IpAddr::V4(ip))) => RData::A(ip),
+        (_, Ok(IpAddr::V6(ip))) => RData::AAAA(ip),
+        ("NS", _) => RData::NS(dns_name(fields[1])),
+        ("MX", _) => RData::MX(MX::new(fields[1].parse().unwrap(), dns_name(fields[2]))),
+        ("TXT", _) => RData::TXT(TXT::new(vec![fields[1..].join(" ")])),
+        ("SRV", _) => RData::SRV(SRV::new(
+            fields[1].parse().unwrap(),
+            fields[2].parse().unwrap(),
+            fields[3].parse().unwrap(),
+            dns_name(fields[4]),
+        )),
+        ("SOA", _) => RData::SOA(SOA::new(
+            dns_name(fields[1]),
+            dns_name(fields[2]),
+            1,
+            3600,
+            600,
+            86400,
+            60,
+        )),
+        _ => RData::CNAME(dns_name(value)),
+    };
+    Record::from_rdata(dns_name(name), 60, rdata)
+}
+
+// exact names win over wildcards
+fn records_of<'a>(records: &'a [(&'static str, &'static str)], name: &str) -> Vec<&'a str> {
+    let exact: Vec<&str> = records
+        .iter()
+        .filter(|(owner, _)| *owner == name)
+        .map(|(_, value)| *value)
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    records
+        .iter()
+        .filter(|(owner, _)| {
+            owner
+                .strip_prefix("*.")
+                .is_some_and(|parent| name.ends_with(&format!(".{}", parent)))
+        })
+        .map(|(_, value)| *value)
+        .collect()
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::time::{sleep, Instant};
+
+// how fast we are allowed to go, None is as fast as the concurrency allows
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Limits {
+    // requests per second to all the hosts together
+    pub rate: Option<f64>,
+    // requests per second to one host
+    pub host_rate: Option<f64>,
+    // random wait up to this long before every request
+    pub jitter: Duration,
+}
+
+// token bucket, holds a second worth of requests so short bursts go through
+struct Bucket {
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        Bucket {
+            rate,
+            tokens: rate.max(1.0),
+            last: Instant::now(),
+        }
+    }
+
+    // takes a token or says how long until there is one
+    fn take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        self.last = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+struct Host {
+    bucket: Option<Bucket>,
+    // set when the host told us to slow down
+    paused_until: Instant,
+}
+
+// every request (http or port connect) waits here for its turn
+pub struct Throttle {
+    limits: Limits,
+    global: Option<Mutex<Bucket>>,
+    hosts: Mutex<HashMap<String, Host>>,
+}
+
+impl Throttle {
+    pub fn new(limits: Limits) -> Self {
+        Throttle {
+            limits,
+            global: limits
+                .rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| Mutex::new(Bucket::new(rate))),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Throttle::new(Limits::default())
+    }
+
+    pub async fn wait(&self, host: &str) {
+        if !self.limits.jitter.is_zero() {
+            let jitter = random_u64() % (self.limits.jitter.as_millis() as u64 + 1);
+            sleep(Duration::from_millis(jitter)).await;
+        }
+        // the host first so a paused host does not eat global tokens
+        while let Some(wait) = self.host_wait(host) {
+            sleep(wait).await;
+        }
+        while let Some(wait) = self.global_wait() {
+            sleep(wait).await;
+        }
+    }
+
+    // the lock is released before sleeping
+    fn global_wait(&self) -> Option<Duration> {
+        let global = self.global.as_ref()?;
+        global.lock().expect("throttle: locking").take()
+    }
+
+    // no request goes to the host for delay
+    pub fn back_off(&self, host: &str, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut hosts = self.hosts.lock().expect("throttle: locking");
+        let host = hosts
+            .entry(host.to_string())
+            .or_insert_with(|| self.new_host());
+        host.paused_until = host.paused_until.max(until);
+    }
+
+    fn host_wait(&self, host: &str) -> Option<Duration> {
+        let mut hosts = self.hosts.lock().expect("throttle: locking");
+        let host = hosts
+            .entry(host.to_string())
+            .or_insert_with(|| self.new_host());
+        let now = Instant::now();
+        if host.paused_until > now {
+            return Some(host.paused_until - now);
+        }
+        host.bucket.as_mut().and_then(Bucket::take)
+    }
+
+    fn new_host(&self) -> Host {
+        Host {
+            bucket: self
+                .limits
+                .host_rate
+                .filter(|rate| *rate > 0.0)
+                .map(Bucket::new),
+            paused_until: Instant::now(),
+        }
+    }
+}
+
+// no rand crate so the std hasher seed does the job
+fn random_u64() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u8(0);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limits, Throttle};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    async fn elapsed(throttle: &Throttle, hosts: &[&str]) -> Duration {
+        let start = Instant::now();
+        for host in hosts {
+            throttle.wait(host).await;
+        }
+        start.elapsed()
+    }
+
+    #[tokio::test]
+    async fn host_rate() {
+        let throttle = Throttle::new(Limits {
+            host_rate: Some(10.0),
+            ..Limits::default()
+        });
+        // the first 10 are the burst, the next 5 wait 100ms each
+        let hosts = ["kerkour.com"; 15];
+        assert!(elapsed(&throttle, &hosts).await >= Duration::from_millis(450));
+        // other hosts have their own bucket
+        assert!(elapsed(&throttle, &["www.kerkour.com"; 5]).await < Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn global_rate() {
+        let throttle = Throttle::new(Limits {
+            rate: Some(5.0),
+            ..Limits::default()
+        });
+        // a second worth of burst, then one every 200ms whatever the host
+        let hosts = ["a.kerkour.com", "b.kerkour.com", "c.kerkour.com"];
+        assert!(elapsed(&throttle, &hosts).await < Duration::from_millis(100));
+        assert!(elapsed(&throttle, &hosts).await >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn back_off_pauses_the_host() {
+        let throttle = Throttle::unlimited();
+        throttle.back_off("kerkour.com", Duration::from_millis(200));
+
+        assert!(elapsed(&throttle, &["www.kerkour.com"]).await < Duration::from_millis(100));
+        assert!(elapsed(&throttle, &["kerkour.com"]).await >= Duration::from_millis(190));
+    }
+
+    #[tokio::test]
+    async fn jitter() {
+        let throttle = Throttle::new(Limits {
+            jitter: Duration::from_millis(20),
+            ..Limits::default()
+        });
+        assert!(elapsed(&throttle, &["kerkour.com"; 5]).await <= Duration::from_millis(200));
+    }
+}
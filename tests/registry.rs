@@ -0,0 +1,60 @@
+// a module added from outside the crate, like a third party would
+use async_trait::async_trait;
+use tricoder::{
+    cli,
+    http_client::HttpClient,
+    modules::{self, Finding, HttpModule, Module, Severity},
+    Error,
+};
+
+struct ServerStatus {}
+
+tricoder::register_http_module!(ServerStatus {});
+
+impl Module for ServerStatus {
+    fn name(&self) -> String {
+        String::from("http/apache_server_status")
+    }
+    fn description(&self) -> String {
+        String::from("looks for apache's mod_status page")
+    }
+    fn tags(&self) -> Vec<&str> {
+        vec!["disclosure"]
+    }
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Low)
+    }
+    fn requests(&self) -> Vec<String> {
+        vec![String::from("GET {endpoint}/server-status")]
+    }
+}
+
+#[async_trait]
+impl HttpModule for ServerStatus {
+    async fn scan(
+        &self,
+        http_client: &HttpClient,
+        endpoint: &str,
+    ) -> Result<Option<Finding>, Error> {
+        let url = format!("{}/server-status", endpoint);
+        let res = http_client.get(&url).send().await?;
+        if res.status().is_success() && res.text().await?.contains("Apache Server Status") {
+            return Ok(Some(Finding::new(self.name(), Severity::Low, url)));
+        }
+        Ok(None)
+    }
+}
+
+#[test]
+fn third_party_module_registered() {
+    assert!(modules::all_http_modules()
+        .iter()
+        .any(|module| module.name() == "http/apache_server_status"));
+    let info = cli::modules_info(&[])
+        .unwrap()
+        .into_iter()
+        .find(|info| info.name == "http/apache_server_status")
+        .unwrap();
+    assert_eq!("http", info.kind);
+    assert_eq!(Some(Severity::Low), info.severity);
+}